@@ -2,27 +2,64 @@ use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
+use crate::auth::{
+    AuthContext, LoginPage, LoginPageProps, RegisterPage, RegisterPageProps, UserNav, UserNavProps,
+};
+use crate::dashboard::{Dashboard, DashboardProps};
+use crate::error_template::{ErrorTemplate, ErrorTemplateProps, NotFound, NotFoundProps};
 use crate::items::{ItemView, ItemViewProps, ItemsView, ItemsViewProps};
+use crate::page_meta::{PageMeta, PageMetaProps, SITE_DESCRIPTION, SITE_NAME};
+use crate::trash::{TrashView, TrashViewProps};
+
+/// The title of a page, whose text leptos_meta writes into the server-rendered head as it is.
+fn page_title(text: String) -> String {
+    #[cfg(feature = "ssr")]
+    let text = escape_attr(&text).into_owned();
+    format!("{text} | {SITE_NAME}")
+}
 
 #[component]
 pub fn App(cx: Scope) -> impl IntoView {
     provide_meta_context(cx);
+    provide_context(cx, AuthContext::new(cx));
     view! {cx,
         <>
             <Link rel="shortcut icon" type_="image/ico" href="/favicon.ico"/>
             <Stylesheet id="leptos" href="/pkg/leptos_playground.css"/>
-            <Meta name="description" content="Leptos playground project."/>
+            <Title formatter=page_title/>
             <Router>
                 <nav>
                     <A exact=true href="/">"Home"</A>
                     <A href="items">"Items"</A>
+                    <UserNav/>
                 </nav>
                 <main>
-                    <Routes>
-                        <Route path="/" view=move |cx| view! {cx, <h1>"Home "</h1>}/>
-                        <Route path="items" view=move |cx| view! {cx, <div><h1>"Items"</h1><ItemsView/></div>}/>
-                        <Route path="items/:id" view=move |cx| view! {cx, <h1>"Item"</h1><ItemView/>}/>
-                    </Routes>
+                    <ErrorBoundary fallback=|cx, errors| view! {cx, <ErrorTemplate errors=errors/>}>
+                        <Routes>
+                            <Route path="/" view=move |cx| view! {cx,
+                                <PageMeta title="Home" description=SITE_DESCRIPTION/>
+                                <Dashboard/>
+                            }/>
+                            <Route path="login" view=move |cx| view! {cx,
+                                <PageMeta title="Log in" description="Log in to the Leptos playground."/>
+                                <LoginPage/>
+                            }/>
+                            <Route path="register" view=move |cx| view! {cx,
+                                <PageMeta title="Register" description="Create an account for the Leptos playground."/>
+                                <RegisterPage/>
+                            }/>
+                            <Route path="items" view=move |cx| view! {cx,
+                                <PageMeta title="Items" description="All items of the Leptos playground."/>
+                                <div><h1>"Items"</h1><ItemsView/></div>
+                            }/>
+                            <Route path="items/trash" view=move |cx| view! {cx,
+                                <PageMeta title="Trash" description="Deleted items, to restore or purge."/>
+                                <TrashView/>
+                            }/>
+                            <Route path="items/:id" view=move |cx| view! {cx, <h1>"Item"</h1><ItemView/>}/>
+                            <Route path="*" view=move |cx| view! {cx, <NotFound/>}/>
+                        </Routes>
+                    </ErrorBoundary>
                 </main>
             </Router>
         </>
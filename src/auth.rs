@@ -0,0 +1,365 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::csrf::{create_csrf_action, get_csrf_token, CsrfActionForm, CsrfActionFormProps};
+use crate::rate_limit::describe_error;
+
+pub const SESSION_COOKIE: &str = "session";
+pub const SESSION_DAYS: u32 = 30;
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// What a user may do, see [crate::authz::Permission].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type), sqlx(rename_all = "lowercase"))]
+pub enum Role {
+    Viewer,
+    #[default]
+    Editor,
+    Admin,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub role: Role,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use argon2::{
+            password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+            Argon2,
+        };
+        use std::sync::OnceLock;
+
+        use http::header::{HeaderMap, HeaderValue, COOKIE, SET_COOKIE};
+        use rand_core::{OsRng, RngCore};
+
+        use crate::authz::AuthError;
+        use crate::item_repository::repository;
+
+        pub fn register_server_functions() {
+            _ = Register::register();
+            _ = Login::register();
+            _ = Logout::register();
+            _ = GetCurrentUser::register();
+        }
+
+        /// Value of the session cookie of a request, if there is one.
+        pub fn session_token(headers: &HeaderMap) -> Option<String> {
+            headers
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(';'))
+                .filter_map(|cookie| cookie.trim().split_once('='))
+                .find(|(name, _)| *name == SESSION_COOKIE)
+                .map(|(_, token)| token.to_string())
+        }
+
+        /// The user of a valid, not expired session.
+        pub async fn user_for_session(token: &str) -> Result<Option<User>, ServerFnError> {
+            repository()
+                .session_user(token)
+                .await
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+
+        /// The logged in user of a request, read from its session cookie.
+        pub async fn user_for_headers(headers: &HeaderMap) -> Result<Option<User>, ServerFnError> {
+            match session_token(headers) {
+                Some(token) => user_for_session(&token).await,
+                None => Ok(None),
+            }
+        }
+
+        /// The logged in user of the request the server function is handling, read from the
+        /// session cookie in the `leptos_axum::RequestParts` context.
+        pub async fn current_user(cx: Scope) -> Result<Option<User>, ServerFnError> {
+            match use_context::<leptos_axum::RequestParts>(cx) {
+                Some(req_parts) => user_for_headers(&req_parts.headers).await,
+                None => Ok(None),
+            }
+        }
+
+        /// Like [current_user], but fails when nobody is logged in.
+        pub async fn require_user(cx: Scope) -> Result<User, ServerFnError> {
+            current_user(cx)
+                .await?
+                .ok_or_else(|| AuthError::LoginRequired.into())
+        }
+
+        fn hash_password(password: &str) -> Result<String, ServerFnError> {
+            let salt = SaltString::generate(&mut OsRng);
+            Argon2::default()
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+
+        fn verify_password(password: &str, password_hash: &str) -> bool {
+            PasswordHash::new(password_hash)
+                .map(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+                .unwrap_or(false)
+        }
+
+        /// A hash no password is checked against but unknown usernames, so that logging in as one
+        /// takes as long as with a wrong password.
+        fn dummy_password_hash() -> &'static str {
+            static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+            DUMMY_HASH.get_or_init(|| hash_password(&random_token()).unwrap_or_default())
+        }
+
+        /// How sessions are handed out, provided to the server functions by `server::app_router`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct SessionConfig {
+            /// Whether the session cookie is `Secure`, off only for serving plain HTTP.
+            pub secure_cookie: bool,
+        }
+
+        impl Default for SessionConfig {
+            fn default() -> Self {
+                SessionConfig { secure_cookie: true }
+            }
+        }
+
+        impl SessionConfig {
+            /// The default, with a cookie that isn't `Secure` if `SESSION_COOKIE_SECURE` is `false`
+            /// or `0`, e.g. in development.
+            pub fn from_env() -> Self {
+                SessionConfig {
+                    secure_cookie: std::env::var("SESSION_COOKIE_SECURE").map_or(true, |v| v != "false" && v != "0"),
+                }
+            }
+        }
+
+        async fn set_session_cookie(cx: Scope, value: &str, max_age: u32) {
+            let config = use_context::<SessionConfig>(cx).unwrap_or_default();
+            let secure = if config.secure_cookie { "; Secure" } else { "" };
+            let cookie = format!("{SESSION_COOKIE}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age}{secure}");
+            match (use_context::<leptos_axum::ResponseOptions>(cx), HeaderValue::from_str(&cookie)) {
+                (Some(response), Ok(cookie)) => response.append_header(SET_COOKIE, cookie).await,
+                _ => log::error!("Could not set the session cookie"),
+            }
+        }
+
+        fn random_token() -> String {
+            let mut bytes = [0u8; 32];
+            OsRng.fill_bytes(&mut bytes);
+            bytes.iter().map(|b| format!("{b:02x}")).collect()
+        }
+
+        async fn start_session(cx: Scope, user_id: i64) -> Result<(), ServerFnError> {
+            let token = random_token();
+            let csrf_token = random_token();
+            repository()
+                .create_session(&token, user_id, &csrf_token, SESSION_DAYS)
+                .await
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+            set_session_cookie(cx, &token, SESSION_DAYS * 24 * 60 * 60).await;
+            Ok(())
+        }
+    }
+}
+
+pub fn validate_registration(username: &str, password: &str, password_confirmation: &str) -> Result<(), String> {
+    if username.trim().is_empty() {
+        Err("username must not be empty".to_string())
+    } else if password.chars().count() < MIN_PASSWORD_LEN {
+        Err(format!("password must be at least {MIN_PASSWORD_LEN} characters"))
+    } else if password != password_confirmation {
+        Err("passwords do not match".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[server(Register, "/api")]
+pub async fn register(
+    cx: Scope,
+    username: String,
+    password: String,
+    password_confirmation: String,
+) -> Result<User, ServerFnError> {
+    let username = username.trim().to_string();
+    validate_registration(&username, &password, &password_confirmation).map_err(ServerFnError::Args)?;
+    let password_hash = hash_password(&password)?;
+
+    let user = repository()
+        .create_user(username, password_hash)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| ServerFnError::Args("username is already taken".to_string()))?;
+
+    start_session(cx, user.id).await?;
+    Ok(user)
+}
+
+#[server(Login, "/api")]
+pub async fn login(cx: Scope, username: String, password: String) -> Result<User, ServerFnError> {
+    let user = repository()
+        .user_by_name(&username)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    // Unknown usernames are checked against a hash as well, or they'd be told apart by the time it takes.
+    let password_hash = match &user {
+        Some((_, password_hash)) => password_hash.as_str(),
+        None => dummy_password_hash(),
+    };
+    let verified = verify_password(&password, password_hash);
+    match user {
+        Some((user, _)) if verified => {
+            start_session(cx, user.id).await?;
+            Ok(user)
+        }
+        _ => Err(ServerFnError::Args("invalid username or password".to_string())),
+    }
+}
+
+#[server(Logout, "/api")]
+pub async fn logout(cx: Scope) -> Result<(), ServerFnError> {
+    let token = use_context::<leptos_axum::RequestParts>(cx)
+        .and_then(|req_parts| session_token(&req_parts.headers));
+    if let Some(token) = token {
+        repository()
+            .delete_session(&token)
+            .await
+            .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    }
+    set_session_cookie(cx, "", 0).await;
+    Ok(())
+}
+
+#[server(GetCurrentUser, "/api")]
+pub async fn get_current_user(cx: Scope) -> Result<Option<User>, ServerFnError> {
+    current_user(cx).await
+}
+
+/// Login state shared by the whole app, provided by `App`.
+#[derive(Copy, Clone)]
+pub struct AuthContext {
+    pub user: Resource<(usize, usize, usize), Option<User>>,
+    pub csrf_token: Resource<(usize, usize, usize), Option<String>>,
+    pub register: Action<Register, Result<User, ServerFnError>>,
+    pub login: Action<Login, Result<User, ServerFnError>>,
+    pub logout: Action<Logout, Result<(), ServerFnError>>,
+}
+
+impl AuthContext {
+    pub fn new(cx: Scope) -> Self {
+        let register = create_csrf_action::<Register>(cx);
+        let login = create_csrf_action::<Login>(cx);
+        let logout = create_csrf_action::<Logout>(cx);
+        let versions = move || {
+            (
+                register.version().get(),
+                login.version().get(),
+                logout.version().get(),
+            )
+        };
+        let user = create_resource(
+            cx,
+            versions,
+            move |_| async move {
+                get_current_user(cx).await.unwrap_or_else(|e| {
+                    log::error!("Error reading current user: {e}");
+                    None
+                })
+            },
+        );
+        let csrf_token = create_resource(
+            cx,
+            versions,
+            move |_| async move {
+                get_csrf_token(cx).await.unwrap_or_else(|e| {
+                    log::error!("Error reading CSRF token: {e}");
+                    None
+                })
+            },
+        );
+        AuthContext {
+            user,
+            csrf_token,
+            register,
+            login,
+            logout,
+        }
+    }
+
+    /// The logged in user, `None` while loading or when logged out.
+    pub fn current_user(&self) -> Option<User> {
+        self.user.read().flatten()
+    }
+}
+
+#[component]
+pub fn UserNav(cx: Scope) -> impl IntoView {
+    let auth = use_context::<AuthContext>(cx).unwrap();
+
+    view! { cx,
+        <Transition fallback=move || ()>
+            {move || match auth.current_user() {
+                Some(user) => view! { cx,
+                    <span>
+                        {user.username}
+                        <CsrfActionForm action=auth.logout>
+                            <input type="submit" value="Logout"/>
+                        </CsrfActionForm>
+                    </span>
+                }.into_view(cx),
+                None => view! { cx,
+                    <span>
+                        <A href="/login">"Login"</A>
+                        <A href="/register">"Register"</A>
+                    </span>
+                }.into_view(cx),
+            }}
+        </Transition>
+    }
+}
+
+#[component]
+pub fn LoginPage(cx: Scope) -> impl IntoView {
+    let auth = use_context::<AuthContext>(cx).unwrap();
+
+    view! { cx,
+        <h1>"Login"</h1>
+        <CsrfActionForm action=auth.login>
+            <label>"Username" <input type="text" name="username" required/></label>
+            <label>"Password" <input type="password" name="password" required/></label>
+            <input type="submit" value="Login"/>
+        </CsrfActionForm>
+        <AuthResult value=Signal::derive(cx, move || auth.login.value().get().map(|res| res.map(|_| ())))/>
+    }
+}
+
+#[component]
+pub fn RegisterPage(cx: Scope) -> impl IntoView {
+    let auth = use_context::<AuthContext>(cx).unwrap();
+
+    view! { cx,
+        <h1>"Register"</h1>
+        <CsrfActionForm action=auth.register>
+            <label>"Username" <input type="text" name="username" required/></label>
+            <label>"Password" <input type="password" name="password" required/></label>
+            <label>"Repeat password" <input type="password" name="password_confirmation" required/></label>
+            <input type="submit" value="Register"/>
+        </CsrfActionForm>
+        <AuthResult value=Signal::derive(cx, move || auth.register.value().get().map(|res| res.map(|_| ())))/>
+    }
+}
+
+#[component]
+fn AuthResult(cx: Scope, value: Signal<Option<Result<(), ServerFnError>>>) -> impl IntoView {
+    move || match value() {
+        Some(Ok(())) => view! { cx, <p>"Welcome! " <A href="/items">"Go to items"</A></p> }.into_view(cx),
+        Some(Err(e)) => view! { cx, <p class="error">{describe_error(&e)}</p> }.into_view(cx),
+        None => ().into_view(cx),
+    }
+}
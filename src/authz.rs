@@ -0,0 +1,204 @@
+use std::fmt;
+
+use cfg_if::cfg_if;
+use leptos::*;
+
+use crate::auth::{Role, User};
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use axum::{
+            body::{Body, HttpBody},
+            http::{Request, StatusCode},
+            middleware::Next,
+            response::{IntoResponse, Response},
+        };
+        use hyper::ext::ReasonPhrase;
+
+        use crate::auth::current_user;
+        use crate::item_store;
+
+        /// Fails with an [AuthError] unless the user of the current request has `permission`.
+        pub async fn authorize(cx: Scope, permission: Permission) -> Result<Option<User>, ServerFnError> {
+            let user = current_user(cx).await?;
+            permission.check(user.as_ref())?;
+            Ok(user)
+        }
+
+        /// Looks up the owner of item `id`, deleted or not, and checks that the current user may modify it.
+        pub async fn authorize_item(cx: Scope, id: i64) -> Result<Option<User>, ServerFnError> {
+            let owner_id = item_store::owner_id(id)
+                .await
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+                .ok_or(AuthError::NotFound)?;
+            authorize(cx, Permission::ModifyItem { owner_id }).await
+        }
+
+        impl AuthError {
+            /// 401 when logged out, 403 when logged in without the permission. Server functions
+            /// answer with [server_fn_rejection] instead, the browser client can't read these.
+            pub fn status_code(&self) -> StatusCode {
+                match self {
+                    AuthError::LoginRequired => StatusCode::UNAUTHORIZED,
+                    AuthError::Forbidden => StatusCode::FORBIDDEN,
+                    AuthError::NotFound => StatusCode::NOT_FOUND,
+                }
+            }
+        }
+
+        /// The status text of a server function that failed for a reason the caller can't act on.
+        pub const INTERNAL_ERROR: &str = "internal error";
+
+        /// A failed server function call with `message` as its status text, the only part of it the
+        /// browser client keeps: the leptos 0.1 client drops the body of failed calls and turns a
+        /// 5xx into a `ServerFnError::ServerError` with the status text. `status` must be a 5xx or
+        /// one of the statuses of [Rejection::has_status](crate::rate_limit::Rejection::has_status),
+        /// whose status text `csrf::create_csrf_action` reads as well.
+        pub fn server_fn_rejection(status: StatusCode, message: &str) -> Response {
+            debug_assert!(
+                status.is_server_error() || crate::rate_limit::Rejection::has_status(status.as_u16()),
+                "the client only reads the status text of a 5xx or a rejection"
+            );
+            let reason: String = message
+                .chars()
+                .map(|c| match c {
+                    c if c.is_ascii_graphic() => c,
+                    c if c.is_whitespace() => ' ',
+                    _ => '?',
+                })
+                .collect();
+            let mut res = (status, message.to_string()).into_response();
+            if let Ok(reason) = ReasonPhrase::try_from(reason) {
+                res.extensions_mut().insert(reason);
+            }
+            res
+        }
+
+        /// Middleware for `/api/*fn_name`: `leptos_axum` answers every failed call with a 500 and
+        /// the error as text, this sends an [AuthError::code] or the message of a
+        /// `ServerFnError::Args`, i.e. a validation error, as the status text so the browser gets
+        /// it too. Any other failure is logged and answered with a generic "internal error", its
+        /// message may tell about the server's internals.
+        pub async fn server_fn_error_status(req: Request<Body>, next: Next<Body>) -> Response {
+            let res = next.run(req).await;
+            if res.status() != StatusCode::INTERNAL_SERVER_ERROR {
+                return res;
+            }
+
+            let mut body = res.into_body();
+            let mut bytes = Vec::new();
+            while let Some(chunk) = body.data().await {
+                match chunk {
+                    Ok(chunk) => bytes.extend_from_slice(&chunk),
+                    Err(e) => {
+                        log::error!("Error reading a failed server function response: {e}");
+                        return server_fn_rejection(StatusCode::INTERNAL_SERVER_ERROR, INTERNAL_ERROR);
+                    }
+                }
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            let server_error = ServerFnError::ServerError(String::new()).to_string();
+            let args_error = ServerFnError::Args(String::new()).to_string();
+            let message = match (
+                text.strip_prefix(server_error.as_str()),
+                text.strip_prefix(args_error.as_str()),
+            ) {
+                (Some(code), _) if AuthError::from_code(code).is_some() => code,
+                (_, Some(message)) => message.trim_start(),
+                _ => {
+                    log::error!("Server function failed: {text}");
+                    INTERNAL_ERROR
+                }
+            };
+            server_fn_rejection(StatusCode::INTERNAL_SERVER_ERROR, message)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    ReadItems,
+    CreateItem,
+    /// Edit, delete, restore or revert an item owned by `owner_id`.
+    ModifyItem { owner_id: Option<i64> },
+}
+
+impl Permission {
+    /// Whether `user`, `None` when logged out, has this permission. Viewers and anonymous users
+    /// can only read, editors can add items and change their own, admins can do everything.
+    pub fn allows(&self, user: Option<&User>) -> bool {
+        match (self, user) {
+            (Permission::ReadItems, _) => true,
+            (_, None) => false,
+            (_, Some(user)) if user.role == Role::Admin => true,
+            (Permission::CreateItem, Some(user)) => user.role == Role::Editor,
+            (Permission::ModifyItem { owner_id }, Some(user)) => {
+                user.role == Role::Editor && *owner_id == Some(user.id)
+            }
+        }
+    }
+
+    /// Like [Permission::allows], but tells why it doesn't.
+    pub fn check(&self, user: Option<&User>) -> Result<(), AuthError> {
+        match (self.allows(user), user) {
+            (true, _) => Ok(()),
+            (false, None) => Err(AuthError::LoginRequired),
+            (false, Some(_)) => Err(AuthError::Forbidden),
+        }
+    }
+}
+
+/// Why a server function refused to run, returned as a `ServerFnError::ServerError` whose message
+/// is the [AuthError::code], which also reaches the browser as the status text of the failed call.
+/// Use [AuthError::from_server_fn_error] to tell it from other errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthError {
+    LoginRequired,
+    Forbidden,
+    /// The item the call is about doesn't exist, or isn't where the call expects it, like an item
+    /// to restore that isn't in the trash.
+    NotFound,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::LoginRequired => write!(f, "login required"),
+            AuthError::Forbidden => write!(f, "forbidden"),
+            AuthError::NotFound => write!(f, "not found"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl AuthError {
+    /// Stable identifier of the error, unlike the message it won't change with the wording.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::LoginRequired => "login_required",
+            AuthError::Forbidden => "forbidden",
+            AuthError::NotFound => "not_found",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        [AuthError::LoginRequired, AuthError::Forbidden, AuthError::NotFound]
+            .into_iter()
+            .find(|e| e.code() == code)
+    }
+
+    /// The [AuthError] a server function failed with, `None` if it failed for another reason.
+    pub fn from_server_fn_error(e: &ServerFnError) -> Option<Self> {
+        match e {
+            ServerFnError::ServerError(message) => AuthError::from_code(message),
+            _ => None,
+        }
+    }
+}
+
+impl From<AuthError> for ServerFnError {
+    fn from(e: AuthError) -> Self {
+        ServerFnError::ServerError(e.code().to_string())
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::BTreeSet;
+
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::item_filter::ItemFilter;
+use crate::items::MockItem;
+use crate::rate_limit::describe_error;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::collections::HashMap;
+
+        use crate::auth::User;
+        use crate::authz::{authorize, AuthError, Permission};
+        use crate::item_repository::repository;
+        use crate::item_store;
+        use crate::items::validate_item;
+
+        pub fn register_server_functions() {
+            _ = BulkDeleteItems::register();
+            _ = BulkUpdateItems::register();
+            _ = BulkExportItems::register();
+        }
+
+        /// Resolves a selection to the ids of the items it covers.
+        async fn selected_ids(selection: &BulkSelection) -> Result<Vec<i64>, ServerFnError> {
+            match selection {
+                BulkSelection::Ids(ids) => Ok(ids.iter().copied().collect()),
+                BulkSelection::AllMatching { filter, except } => {
+                    let ids = repository()
+                        .matching_ids(filter)
+                        .await
+                        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+                    Ok(ids.into_iter().filter(|id| !except.contains(id)).collect())
+                }
+            }
+        }
+
+        /// The selected (not deleted) items by id.
+        async fn selected_items(ids: &[i64]) -> Result<HashMap<i64, MockItem>, ServerFnError> {
+            let items = repository()
+                .get_many(ids)
+                .await
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+            Ok(items.into_iter().map(|item| (item.id, item)).collect())
+        }
+
+        /// Why `user` may not modify `item`, `None` if they may.
+        fn permission_error(user: Option<&User>, item: Option<&MockItem>) -> Option<String> {
+            match item {
+                None => Some("item not found".to_string()),
+                Some(item) if !Permission::ModifyItem { owner_id: item.owner_id }.allows(user) => {
+                    Some(AuthError::Forbidden.to_string())
+                }
+                Some(_) => None,
+            }
+        }
+
+        /// Why `item` would be invalid with `title` and `description` set, the rules of
+        /// `UpdateItem` applied to the fields that stay as they are too. `None` if it stays valid.
+        fn validation_error(item: &MockItem, title: Option<&String>, description: Option<&String>) -> Option<String> {
+            validate_item(
+                title.unwrap_or(&item.title),
+                description.unwrap_or(&item.description),
+            )
+            .err()
+            .map(|errors| errors.join(", "))
+        }
+    }
+}
+
+/// The items a bulk action applies to, either picked one by one or every item matching the
+/// current list view, minus the ones unchecked afterwards.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BulkSelection {
+    Ids(BTreeSet<i64>),
+    AllMatching {
+        filter: ItemFilter,
+        except: BTreeSet<i64>,
+    },
+}
+
+impl Default for BulkSelection {
+    fn default() -> Self {
+        BulkSelection::Ids(BTreeSet::new())
+    }
+}
+
+impl BulkSelection {
+    pub fn contains(&self, id: i64) -> bool {
+        match self {
+            BulkSelection::Ids(ids) => ids.contains(&id),
+            BulkSelection::AllMatching { except, .. } => !except.contains(&id),
+        }
+    }
+    pub fn set(&mut self, id: i64, selected: bool) {
+        let (set, insert) = match self {
+            BulkSelection::Ids(ids) => (ids, selected),
+            BulkSelection::AllMatching { except, .. } => (except, !selected),
+        };
+        if insert {
+            set.insert(id);
+        } else {
+            set.remove(&id);
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        matches!(self, BulkSelection::Ids(ids) if ids.is_empty())
+    }
+    pub fn is_all_matching(&self) -> bool {
+        matches!(self, BulkSelection::AllMatching { .. })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkItemResult {
+    pub id: i64,
+    pub error: Option<String>,
+}
+
+#[server(BulkDeleteItems, "/api", "Cbor")]
+pub async fn bulk_delete_items(
+    cx: Scope,
+    selection: BulkSelection,
+) -> Result<Vec<BulkItemResult>, ServerFnError> {
+    let user = authorize(cx, Permission::CreateItem).await?;
+    let changed_by = user.as_ref().map(|user| user.id);
+    let ids = selected_ids(&selection).await?;
+    let items = selected_items(&ids).await?;
+
+    let mut errors = HashMap::new();
+    let mut allowed = Vec::new();
+    for id in &ids {
+        match permission_error(user.as_ref(), items.get(id)) {
+            Some(error) => {
+                errors.insert(*id, error);
+            }
+            None => allowed.push(*id),
+        }
+    }
+    let deleted = item_store::soft_delete_all(&allowed, changed_by)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    Ok(ids
+        .into_iter()
+        .map(|id| {
+            let error = errors
+                .remove(&id)
+                .or_else(|| (!deleted.contains(&id)).then(|| "item not found".to_string()));
+            BulkItemResult { id, error }
+        })
+        .collect())
+}
+
+/// Sets `title` and/or `description` on every selected item, fields that are `None` are left as they are.
+/// Items that would become invalid are left alone and reported with their validation errors.
+#[server(BulkUpdateItems, "/api", "Cbor")]
+pub async fn bulk_update_items(
+    cx: Scope,
+    selection: BulkSelection,
+    title: Option<String>,
+    description: Option<String>,
+) -> Result<Vec<BulkItemResult>, ServerFnError> {
+    let user = authorize(cx, Permission::CreateItem).await?;
+    let changed_by = user.as_ref().map(|user| user.id);
+    let ids = selected_ids(&selection).await?;
+    let items = selected_items(&ids).await?;
+
+    let mut errors = HashMap::new();
+    let mut allowed = Vec::new();
+    for id in &ids {
+        let item = items.get(id);
+        let error = permission_error(user.as_ref(), item).or_else(|| {
+            item.and_then(|item| validation_error(item, title.as_ref(), description.as_ref()))
+        });
+        match error {
+            Some(error) => {
+                errors.insert(*id, error);
+            }
+            None => allowed.push(*id),
+        }
+    }
+    let updated = item_store::update_all(&allowed, title, description, changed_by)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    Ok(ids
+        .into_iter()
+        .map(|id| {
+            let error = errors
+                .remove(&id)
+                .or_else(|| (!updated.iter().any(|item| item.id == id)).then(|| "item not found".to_string()));
+            BulkItemResult { id, error }
+        })
+        .collect())
+}
+
+/// Anyone who can see the list can export from it, like with its export links.
+#[server(BulkExportItems, "/api", "Cbor")]
+pub async fn bulk_export_items(cx: Scope, selection: BulkSelection) -> Result<Vec<MockItem>, ServerFnError> {
+    authorize(cx, Permission::ReadItems).await?;
+    let ids = selected_ids(&selection).await?;
+    repository()
+        .get_many(&ids)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[component]
+pub fn BulkSelectCheckbox(cx: Scope, selection: RwSignal<BulkSelection>, id: i64) -> impl IntoView {
+    view! { cx,
+        <input type="checkbox"
+            prop:checked=move || selection.with(|s| s.contains(id))
+            on:change=move |e| selection.update(|s| s.set(id, event_target_checked(&e)))/>
+    }
+}
+
+#[component]
+pub fn BulkActionBar(
+    cx: Scope,
+    selection: RwSignal<BulkSelection>,
+    filter: Signal<ItemFilter>,
+    page_ids: Signal<Vec<i64>>,
+    /// Whether deleting and updating are offered, exporting always is.
+    can_edit: Signal<bool>,
+    bulk_delete: Action<BulkDeleteItems, Result<Vec<BulkItemResult>, ServerFnError>>,
+    bulk_update: Action<BulkUpdateItems, Result<Vec<BulkItemResult>, ServerFnError>>,
+    bulk_export: Action<BulkExportItems, Result<Vec<MockItem>, ServerFnError>>,
+) -> impl IntoView {
+    let (title, set_title) = create_signal(cx, String::new());
+    let (description, set_description) = create_signal(cx, String::new());
+
+    let all_on_page_selected = move || {
+        let ids = page_ids();
+        !ids.is_empty() && selection.with(|s| ids.iter().all(|id| s.contains(*id)))
+    };
+    let select_page = move |e: web_sys::Event| {
+        let checked = event_target_checked(&e);
+        selection.update(|s| page_ids().into_iter().for_each(|id| s.set(id, checked)));
+    };
+    let select_all_matching = move |e: web_sys::Event| {
+        selection.set(if event_target_checked(&e) {
+            BulkSelection::AllMatching {
+                filter: filter(),
+                except: BTreeSet::new(),
+            }
+        } else {
+            BulkSelection::default()
+        });
+    };
+    let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+
+    let results = move |value: Option<Result<Vec<BulkItemResult>, ServerFnError>>, what: &str| {
+        value.map(|res| match res {
+            Ok(results) => {
+                let failed: Vec<_> = results.iter().filter(|r| r.error.is_some()).cloned().collect();
+                let succeeded = results.len() - failed.len();
+                view! { cx, <div>
+                    <p>{format!("{what}: {succeeded} succeeded, {} failed", failed.len())}</p>
+                    <ul>
+                        {failed
+                            .into_iter()
+                            .map(|r| view! { cx, <li class="error">{format!("[{}] {}", r.id, r.error.unwrap_or_default())}</li> })
+                            .collect::<Vec<_>>()}
+                    </ul>
+                </div>}
+                .into_view(cx)
+            }
+            Err(e) => view! { cx, <p class="error">{format!("{what} failed: {}", describe_error(&e))}</p> }.into_view(cx),
+        })
+    };
+    let export_link = move || {
+        bulk_export.value().get().map(|res| match res {
+            Ok(items) => {
+                let json = serde_json::to_string_pretty(&items).unwrap_or_default();
+                let href = format!(
+                    "data:application/json;charset=utf-8,{}",
+                    js_sys::encode_uri_component(&json)
+                );
+                view! { cx, <a href=href download="items.json">{format!("download {} items", items.len())}</a> }.into_view(cx)
+            }
+            Err(e) => view! { cx, <p class="error">{format!("Export failed: {}", describe_error(&e))}</p> }.into_view(cx),
+        })
+    };
+
+    view! { cx,
+        <div class="bulk-actions">
+            <label>
+                <input type="checkbox" prop:checked=all_on_page_selected on:change=select_page/>
+                "select all on page"
+            </label>
+            <label>
+                <input type="checkbox" prop:checked=move || selection.with(BulkSelection::is_all_matching) on:change=select_all_matching/>
+                "select all matching"
+            </label>
+            <button
+                disabled=move || selection.with(BulkSelection::is_empty)
+                on:click=move |_| bulk_export.dispatch(BulkExportItems { selection: selection.get() })>
+                "Export selected"
+            </button>
+            {move || can_edit().then(|| view! { cx, <div>
+                <button
+                    disabled=move || selection.with(BulkSelection::is_empty)
+                    on:click=move |_| bulk_delete.dispatch(BulkDeleteItems { selection: selection.get() })>
+                    "Delete selected"
+                </button>
+                <div>
+                    <label>"Title" <input type="text" prop:value=title on:input=move |e| set_title(event_target_value(&e))/></label>
+                    <label>"Description" <input type="text" prop:value=description on:input=move |e| set_description(event_target_value(&e))/></label>
+                    <button
+                        disabled=move || selection.with(BulkSelection::is_empty) || (title().is_empty() && description().is_empty())
+                        on:click=move |_| bulk_update.dispatch(BulkUpdateItems {
+                            selection: selection.get(),
+                            title: non_empty(title()),
+                            description: non_empty(description()),
+                        })>
+                        "Update selected"
+                    </button>
+                </div>
+            </div>})}
+            {move || results(bulk_delete.value().get(), "Delete")}
+            {move || results(bulk_update.value().get(), "Update")}
+            {export_link}
+        </div>
+    }
+}
@@ -0,0 +1,369 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use leptos_router::*;
+
+use crate::auth::AuthContext;
+
+/// Name of the hidden form field carrying the CSRF token of the session.
+pub const CSRF_FIELD: &str = "csrf_token";
+/// Header carrying the CSRF token of requests that aren't posted forms, like the import upload
+/// and the calls of [create_csrf_action].
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::sync::Arc;
+
+        use axum::{
+            body::{Body, Bytes},
+            extract::{FromRequest, State},
+            http::{header, HeaderMap, Method, Request, StatusCode},
+            middleware::Next,
+            response::{IntoResponse, Response},
+        };
+
+        use crate::auth::{session_token, GetCurrentUser};
+        use crate::bulk::BulkExportItems;
+        use crate::dashboard::GetItemStats;
+        use crate::history::GetItemRevisions;
+        use crate::item_repository::repository;
+        use crate::items::{GetItem, GetItems};
+        use crate::security_headers::CSP_REPORT_PATH;
+        use crate::trash::GetDeletedItems;
+
+        /// The only body that carries the token in a [CSRF_FIELD] rather than the [CSRF_HEADER].
+        const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+        pub fn register_server_functions() {
+            _ = GetCsrfToken::register();
+        }
+
+        fn server_fn_path<F: ServerFn>() -> String {
+            format!("{}/{}", F::prefix(), F::url())
+        }
+
+        fn read_only_server_fns() -> [String; 8] {
+            [
+                server_fn_path::<GetItems>(),
+                server_fn_path::<GetItem>(),
+                server_fn_path::<GetItemStats>(),
+                server_fn_path::<BulkExportItems>(),
+                server_fn_path::<GetDeletedItems>(),
+                server_fn_path::<GetItemRevisions>(),
+                server_fn_path::<GetCurrentUser>(),
+                server_fn_path::<GetCsrfToken>(),
+            ]
+        }
+
+        /// Server functions that only read, resources call them without a token.
+        pub fn is_read_only(path: &str) -> bool {
+            read_only_server_fns().iter().any(|read_only| read_only == path)
+        }
+
+        /// Which requests [csrf_protection] lets through without a token.
+        #[derive(Clone, Debug)]
+        pub struct CsrfConfig {
+            /// Paths of non-GET routes that don't need a token, their origin is still checked.
+            pub exempt_paths: Vec<String>,
+            /// Paths of non-GET routes that aren't checked at all, for requests a browser sends on its
+            /// own and without an `Origin`, which don't act for the user.
+            pub unchecked_paths: Vec<String>,
+        }
+
+        impl Default for CsrfConfig {
+            /// Exempts the server functions that only read, and leaves CSP reports unchecked.
+            fn default() -> Self {
+                CsrfConfig {
+                    exempt_paths: read_only_server_fns().into(),
+                    unchecked_paths: vec![CSP_REPORT_PATH.to_string()],
+                }
+            }
+        }
+
+        async fn csrf_token_for_session(session: &str) -> Result<Option<String>, ServerFnError> {
+            repository()
+                .session_csrf_token(session)
+                .await
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+
+        /// `host[:port]` of an `Origin` or `Referer` header value.
+        fn url_host(url: &str) -> Option<&str> {
+            let (_, rest) = url.split_once("://")?;
+            rest.split('/').next()
+        }
+
+        /// Whether the `Origin` (or, without one, `Referer`) of a request names our own host. Browsers
+        /// send one with every write, a request without either can't be told apart from a forged one.
+        fn same_origin(headers: &HeaderMap) -> bool {
+            let host = headers.get(header::HOST).and_then(|host| host.to_str().ok());
+            let source = headers
+                .get(header::ORIGIN)
+                .or_else(|| headers.get(header::REFERER))
+                .and_then(|value| value.to_str().ok());
+            match (source, host) {
+                (Some(source), Some(host)) => url_host(source) == Some(host),
+                _ => false,
+            }
+        }
+
+        fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+        }
+
+        fn forbidden(reason: &str) -> Response {
+            log::warn!("Rejected server function call: {reason}");
+            (StatusCode::FORBIDDEN, reason.to_string()).into_response()
+        }
+
+        /// Middleware for the whole app: checks the origin of every request that isn't a GET, HEAD
+        /// or OPTIONS or to one of the [CsrfConfig::unchecked_paths], and unless its path is in [CsrfConfig::exempt_paths], that a logged in user's
+        /// request carries the token of their session in the [CSRF_HEADER], or, for a posted form,
+        /// in its [CSRF_FIELD]. The field is taken out of the form before it reaches the handler.
+        pub async fn csrf_protection(
+            State(config): State<Arc<CsrfConfig>>,
+            req: Request<Body>,
+            next: Next<Body>,
+        ) -> Response {
+            if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+                || config.unchecked_paths.iter().any(|path| path == req.uri().path())
+            {
+                return next.run(req).await;
+            }
+            if !same_origin(req.headers()) {
+                return forbidden("missing or cross-origin Origin");
+            }
+
+            let session = session_token(req.headers());
+            let exempt = config.exempt_paths.iter().any(|path| path == req.uri().path());
+            let expected = match (exempt, session) {
+                (false, Some(session)) => match csrf_token_for_session(&session).await {
+                    Ok(expected) => expected,
+                    Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+                },
+                _ => None,
+            };
+            let Some(expected) = expected else {
+                return next.run(req).await;
+            };
+            if let Some(token) = req.headers().get(CSRF_HEADER) {
+                if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+                    return next.run(req).await;
+                }
+                return forbidden("missing or invalid CSRF token");
+            }
+            let is_form = req
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .map_or(false, |content_type| content_type.starts_with(FORM_CONTENT_TYPE));
+            if !is_form {
+                return forbidden("missing or invalid CSRF token");
+            }
+
+            let (mut parts, body) = req.into_parts();
+            let body = match Bytes::from_request(Request::new(body), &()).await {
+                Ok(body) => body,
+                Err(rejection) => return rejection.into_response(),
+            };
+            let valid = form_urlencoded::parse(&body)
+                .find(|(name, _)| name == CSRF_FIELD)
+                .map_or(false, |(_, token)| constant_time_eq(token.as_bytes(), expected.as_bytes()));
+            if !valid {
+                return forbidden("missing or invalid CSRF token");
+            }
+            let form = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(form_urlencoded::parse(&body).filter(|(name, _)| name != CSRF_FIELD))
+                .finish();
+            parts.headers.remove(header::CONTENT_LENGTH);
+            next.run(Request::from_parts(parts, Body::from(form))).await
+        }
+    } else {
+        use leptos::js_sys::Uint8Array;
+        use serde::de::DeserializeOwned;
+        use wasm_bindgen::JsCast;
+
+        use crate::rate_limit::Rejection;
+
+        /// The token of the session once the [AuthContext] has it, looked up when a call is made:
+        /// the context's own actions exist before the context is provided.
+        fn session_csrf_token(cx: Scope) -> Option<String> {
+            use_context::<AuthContext>(cx).and_then(|auth| auth.csrf_token.read().flatten())
+        }
+
+        /// Calls server function `F` the way `leptos::call_server_fn` does, with `csrf_token` in
+        /// the [CSRF_HEADER]. The status text of a [Rejection] is kept like that of a 5xx.
+        async fn call_with_csrf_token<F>(args: F, csrf_token: Option<String>) -> Result<F::Output, ServerFnError>
+        where
+            F: ServerFn,
+            F::Output: DeserializeOwned,
+        {
+            let url = format!("{}/{}", F::prefix(), F::url());
+            let request = gloo_net::http::Request::post(&url).header(CSRF_HEADER, &csrf_token.unwrap_or_default());
+            let request = match F::encoding() {
+                Encoding::Url => {
+                    let body = serde_urlencoded::to_string(&args)
+                        .map_err(|e| ServerFnError::Serialization(e.to_string()))?;
+                    request
+                        .header("Content-Type", "application/x-www-form-urlencoded")
+                        .header("Accept", "application/x-www-form-urlencoded")
+                        .body(body)
+                }
+                Encoding::Cbor => {
+                    let mut body = Vec::new();
+                    ciborium::ser::into_writer(&args, &mut body)
+                        .map_err(|e| ServerFnError::Serialization(e.to_string()))?;
+                    request
+                        .header("Content-Type", "application/cbor")
+                        .header("Accept", "application/cbor")
+                        .body(Uint8Array::from(body.as_slice()).buffer())
+                }
+            };
+            let res = request.send().await.map_err(|e| ServerFnError::Request(e.to_string()))?;
+            if (500..=599).contains(&res.status()) || Rejection::has_status(res.status()) {
+                return Err(ServerFnError::ServerError(res.status_text()));
+            }
+            match F::encoding() {
+                Encoding::Url => {
+                    let text = res.text().await.map_err(|e| ServerFnError::Deserialization(e.to_string()))?;
+                    serde_json::from_str(&text).map_err(|e| ServerFnError::Deserialization(e.to_string()))
+                }
+                Encoding::Cbor => {
+                    let binary = res.binary().await.map_err(|e| ServerFnError::Deserialization(e.to_string()))?;
+                    ciborium::de::from_reader(binary.as_slice())
+                        .map_err(|e| ServerFnError::Deserialization(e.to_string()))
+                }
+            }
+        }
+
+        /// The arguments a submitted form holds, read like `ActionForm` reads them.
+        fn form_input<I: DeserializeOwned>(ev: &web_sys::SubmitEvent) -> Result<I, String> {
+            let form = ev
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlFormElement>().ok())
+                .ok_or("submitted by something else than a form")?;
+            let form_data = web_sys::FormData::new_with_form(&form).map_err(|e| format!("{e:?}"))?;
+            let params = web_sys::UrlSearchParams::new_with_str_sequence_sequence(&form_data)
+                .map_err(|e| format!("{e:?}"))?;
+            serde_urlencoded::from_str(&String::from(params.to_string())).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// `create_server_action` whose calls from the browser carry the session's CSRF token in the
+/// [CSRF_HEADER], for every action that changes something. Dispatching it from code or from a
+/// [CsrfActionForm] both pass the check.
+pub fn create_csrf_action<F>(cx: Scope) -> Action<F, Result<F::Output, ServerFnError>>
+where
+    F: Clone + ServerFn,
+    F::Output: serde::de::DeserializeOwned,
+{
+    cfg_if! {
+        if #[cfg(feature = "ssr")] {
+            create_action(cx, move |args: &F| F::call_fn(args.clone(), cx)).using_server_fn::<F>()
+        } else {
+            create_action(cx, move |args: &F| call_with_csrf_token(args.clone(), session_csrf_token(cx)))
+            .using_server_fn::<F>()
+        }
+    }
+}
+
+/// The [create_csrf_action] of a [CsrfMultiActionForm]: `MultiActionForm` dispatches its input
+/// as arguments, which leaves the form's hidden token behind.
+pub fn create_csrf_multi_action<F>(cx: Scope) -> MultiAction<F, Result<F::Output, ServerFnError>>
+where
+    F: Clone + ServerFn,
+    F::Output: serde::de::DeserializeOwned,
+{
+    cfg_if! {
+        if #[cfg(feature = "ssr")] {
+            create_multi_action(cx, move |args: &F| F::call_fn(args.clone(), cx)).using_server_fn::<F>()
+        } else {
+            create_multi_action(cx, move |args: &F| call_with_csrf_token(args.clone(), session_csrf_token(cx)))
+            .using_server_fn::<F>()
+        }
+    }
+}
+
+/// CSRF token of the current session, `None` when not logged in.
+#[server(GetCsrfToken, "/api")]
+pub async fn get_csrf_token(cx: Scope) -> Result<Option<String>, ServerFnError> {
+    let Some(req_parts) = use_context::<leptos_axum::RequestParts>(cx) else {
+        return Ok(None);
+    };
+    match session_token(&req_parts.headers) {
+        Some(session) => csrf_token_for_session(&session).await,
+        None => Ok(None),
+    }
+}
+
+/// Hidden input with the session's CSRF token, rendered by [CsrfActionForm] and [CsrfMultiActionForm].
+#[component]
+pub fn CsrfToken(cx: Scope) -> impl IntoView {
+    let auth = use_context::<AuthContext>(cx).unwrap();
+
+    view! { cx,
+        <Transition fallback=move || ()>
+            <input type="hidden" name=CSRF_FIELD
+                value=move || auth.csrf_token.read().flatten().unwrap_or_default()/>
+        </Transition>
+    }
+}
+
+/// Form of a server function that carries the session's CSRF token, use it for every server
+/// function form. Unlike `ActionForm`, which posts the form itself and can't tell the failures
+/// apart, it dispatches `action`, from [create_csrf_action], with the form's input; without
+/// JavaScript the form is posted with its hidden token.
+#[component]
+pub fn CsrfActionForm<I, O>(
+    cx: Scope,
+    action: Action<I, Result<O, ServerFnError>>,
+    children: Box<dyn FnOnce(Scope) -> Fragment>,
+) -> impl IntoView
+where
+    I: Clone + ServerFn + 'static,
+    O: Clone + Serializable + 'static,
+{
+    let on_submit = move |ev: web_sys::SubmitEvent| {
+        cfg_if! {
+            if #[cfg(not(feature = "ssr"))] {
+                match form_input::<I>(&ev) {
+                    Ok(input) => {
+                        ev.prevent_default();
+                        action.dispatch(input);
+                    }
+                    Err(e) => log::error!("Error reading the form of {}: {e}", I::url()),
+                }
+            } else {
+                _ = ev;
+            }
+        }
+    };
+
+    view! { cx,
+        <form method="POST" action=action.url().unwrap_or_default() on:submit=on_submit>
+            <CsrfToken/>
+            {children(cx)}
+        </form>
+    }
+}
+
+/// `MultiActionForm` that carries the session's CSRF token, its `action` comes from
+/// [create_csrf_multi_action].
+#[component]
+pub fn CsrfMultiActionForm<I, O>(
+    cx: Scope,
+    action: MultiAction<I, Result<O, ServerFnError>>,
+    children: Box<dyn FnOnce(Scope) -> Fragment>,
+) -> impl IntoView
+where
+    I: Clone + ServerFn + 'static,
+    O: Clone + Serializable + 'static,
+{
+    view! { cx,
+        <MultiActionForm action=action>
+            <CsrfToken/>
+            {children(cx)}
+        </MultiActionForm>
+    }
+}
@@ -0,0 +1,182 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::feed::ATOM_FEED_PATH;
+use crate::item_filter::{ItemFilter, ItemSort};
+
+/// Days covered by [ItemStats::added_per_day], today included.
+pub const STATS_DAYS: u32 = 30;
+/// Items in each of the recently added and recently edited lists.
+pub const RECENT_ITEMS: u32 = 5;
+
+const CHART_WIDTH: u32 = 600;
+const CHART_HEIGHT: u32 = 120;
+/// Room below the bars for the date labels.
+const CHART_LABEL_HEIGHT: u32 = 20;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::authz::{authorize, Permission};
+        use crate::item_repository::repository;
+
+        pub fn register_server_functions() {
+            _ = GetItemStats::register();
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct DayCount {
+    /// `YYYY-MM-DD`, in UTC like the timestamps in `item_revisions`.
+    pub day: String,
+    pub count: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct ItemActivity {
+    pub id: i64,
+    pub title: String,
+    /// When the item was added or last edited, `None` for items older than `item_revisions`.
+    pub at: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStats {
+    /// Items not in the trash.
+    pub total: i64,
+    /// One entry per day of the last [STATS_DAYS] days, oldest first, days without items included.
+    pub added_per_day: Vec<DayCount>,
+    pub recently_added: Vec<ItemActivity>,
+    pub recently_edited: Vec<ItemActivity>,
+}
+
+#[server(GetItemStats, "/api")]
+pub async fn get_item_stats(cx: Scope) -> Result<ItemStats, ServerFnError> {
+    authorize(cx, Permission::ReadItems).await?;
+    repository()
+        .item_stats(STATS_DAYS, RECENT_ITEMS)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// The home page: item counts, a chart of the items added per day and the latest activity.
+#[component]
+pub fn Dashboard(cx: Scope) -> impl IntoView {
+    let stats = create_resource(cx, || (), move |_| async move { get_item_stats(cx).await });
+
+    view! { cx,
+        <div class="dashboard">
+            <h1>"Dashboard"</h1>
+            <QuickLinks/>
+            <Transition fallback=move || view! {cx, <p>"Loading..."</p> }>
+                {move || stats.read().map(|stats| match stats {
+                    Ok(stats) => view! { cx,
+                        <p class="total">{format!("{} items", stats.total)}</p>
+                        <h2>{format!("Added in the last {STATS_DAYS} days")}</h2>
+                        <ItemsPerDayChart days=stats.added_per_day/>
+                        <h2>"Recently added"</h2>
+                        <ActivityList items=stats.recently_added/>
+                        <h2>"Recently edited"</h2>
+                        <ActivityList items=stats.recently_edited/>
+                    }.into_view(cx),
+                    Err(e) => view! { cx, <pre class="error">"Server Error: " {e.to_string()}</pre>}.into_view(cx),
+                })}
+            </Transition>
+        </div>
+    }
+}
+
+/// Links into the items list with a filter applied.
+#[component]
+fn QuickLinks(cx: Scope) -> impl IntoView {
+    let links = [
+        ("Newest first", ItemSort::IdDesc),
+        ("Title A-Z", ItemSort::TitleAsc),
+        ("Title Z-A", ItemSort::TitleDesc),
+    ];
+
+    view! { cx,
+        <nav class="quick-links">
+            {links
+                .into_iter()
+                .map(|(label, sort)| {
+                    let filter = ItemFilter { q: None, sort };
+                    view! { cx, <A href=format!("/items?{}", filter.to_query_string())>{label}</A>" " }
+                })
+                .collect::<Vec<_>>()}
+            <A href="/items/trash">"Trash"</A>" "
+            <a href=ATOM_FEED_PATH>"Atom feed"</a>
+        </nav>
+    }
+}
+
+#[component]
+fn ActivityList(cx: Scope, items: Vec<ItemActivity>) -> impl IntoView {
+    if items.is_empty() {
+        return view! { cx, <p>"Nothing yet."</p> }.into_view(cx);
+    }
+    view! { cx,
+        <ul>
+            {items
+                .into_iter()
+                .map(|item| view! { cx,
+                    <li>
+                        <A href=format!("/items/{}", item.id)>{item.title}</A>
+                        {item.at.map(|at| format!(" at {at}"))}
+                    </li>
+                })
+                .collect::<Vec<_>>()}
+        </ul>
+    }
+    .into_view(cx)
+}
+
+/// Bar chart of [ItemStats::added_per_day], rendered as plain SVG so it shows without the wasm.
+#[component]
+pub fn ItemsPerDayChart(cx: Scope, days: Vec<DayCount>) -> impl IntoView {
+    let max = days.iter().map(|day| day.count).max().unwrap_or(0).max(1);
+    let bar_width = CHART_WIDTH as f64 / days.len().max(1) as f64;
+    let total: i64 = days.iter().map(|day| day.count).sum();
+    let label = format!("{total} items added in the last {} days, at most {max} a day", days.len());
+    let first_day = days.first().map(|day| day.day.clone()).unwrap_or_default();
+    let last_day = days.last().map(|day| day.day.clone()).unwrap_or_default();
+
+    let bars = days
+        .into_iter()
+        .enumerate()
+        .map(|(i, day)| {
+            let height = day.count as f64 / max as f64 * CHART_HEIGHT as f64;
+            view! { cx,
+                <rect
+                    class="bar"
+                    x=format!("{:.1}", i as f64 * bar_width + 1.0)
+                    y=format!("{:.1}", CHART_HEIGHT as f64 - height)
+                    width=format!("{:.1}", (bar_width - 2.0).max(1.0))
+                    height=format!("{height:.1}")
+                    data-day=day.day
+                    data-count=day.count.to_string()
+                />
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! { cx,
+        <svg
+            class="items-per-day"
+            role="img"
+            aria-label=label
+            width=CHART_WIDTH.to_string()
+            height=(CHART_HEIGHT + CHART_LABEL_HEIGHT).to_string()
+            viewBox=format!("0 0 {CHART_WIDTH} {}", CHART_HEIGHT + CHART_LABEL_HEIGHT)
+        >
+            {bars}
+            <text x="0" y=(CHART_HEIGHT + CHART_LABEL_HEIGHT - 4).to_string()>{first_day}</text>
+            <text x=CHART_WIDTH.to_string() y=(CHART_HEIGHT + CHART_LABEL_HEIGHT - 4).to_string() text-anchor="end">{last_day}</text>
+            <text x="0" y="12">{format!("max {max}")}</text>
+        </svg>
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use http::StatusCode;
+use leptos::*;
+use leptos_meta::*;
+
+/// Errors of the app itself, rendered by [ErrorTemplate] with the matching status code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AppError {
+    NotFound,
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound => write!(f, "page not found"),
+            AppError::Internal(message) => write!(f, "internal server error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+fn status_code(error: &(dyn std::error::Error + 'static)) -> StatusCode {
+    error
+        .downcast_ref::<AppError>()
+        .map_or(StatusCode::INTERNAL_SERVER_ERROR, AppError::status_code)
+}
+
+/// Errors caught by an `ErrorBoundary`, or `outside_errors` when rendered outside of one, like
+/// the server's fallback handler does. Doesn't need a router.
+#[component]
+pub fn ErrorTemplate(
+    cx: Scope,
+    #[prop(optional)] outside_errors: Option<Errors>,
+    #[prop(optional_no_strip)] errors: Option<RwSignal<Errors>>,
+) -> impl IntoView {
+    let errors = match outside_errors {
+        Some(outside_errors) => create_rw_signal(cx, outside_errors),
+        None => errors.unwrap_or_else(|| create_rw_signal(cx, Errors::default())),
+    };
+
+    let error_list = move || {
+        errors.with(|errors| {
+            errors
+                .0
+                .values()
+                .map(|error| {
+                    let status = status_code(&**error);
+                    view! { cx,
+                        <h2>{status.to_string()}</h2>
+                        <p>{error.to_string()}</p>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // The status of the first error, e.g. "404 Not Found".
+    let title = move || {
+        errors.with(|errors| {
+            errors
+                .0
+                .values()
+                .next()
+                .map_or(StatusCode::INTERNAL_SERVER_ERROR, |error| status_code(&**error))
+                .to_string()
+        })
+    };
+
+    view! { cx,
+        <Title text=title/>
+        <div class="error">
+            <h1>"Something went wrong"</h1>
+            {error_list}
+            <a href="/">"Back to the home page"</a>
+        </div>
+    }
+}
+
+/// The `*` route of `App`, for paths no other route matches.
+#[component]
+pub fn NotFound(cx: Scope) -> impl IntoView {
+    let mut errors = Errors::default();
+    errors.insert(Default::default(), AppError::NotFound);
+    view! { cx, <ErrorTemplate outside_errors=errors/> }
+}
@@ -0,0 +1,164 @@
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+pub const EXPORT_ITEMS_PATH: &str = "/export/items";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Json => "application/json",
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::io;
+
+        use axum::{
+            body::StreamBody,
+            extract::Query,
+            http::{header, HeaderMap, StatusCode},
+            response::IntoResponse,
+        };
+        use futures::stream;
+        use tokio::sync::mpsc;
+
+        use crate::auth::user_for_headers;
+        use crate::authz::Permission;
+        use crate::item_filter::ItemFilter;
+        use crate::item_repository::repository;
+        use crate::items::MockItem;
+
+        /// Rows are buffered in this many chunks between the database task and the response body.
+        const EXPORT_BUFFER: usize = 32;
+        /// Items read from the repository at a time.
+        const EXPORT_BATCH: u32 = 500;
+
+        #[derive(Debug, Default, Deserialize)]
+        #[serde(default)]
+        pub struct ExportParams {
+            format: ExportFormat,
+            #[serde(flatten)]
+            filter: ItemFilter,
+        }
+
+        /// The columns of the CSV export, the fields of [MockItem] in the order they are serialized.
+        const CSV_HEADER: [&str; 4] = ["id", "title", "description", "owner_id"];
+
+        /// The CSV line `write` writes.
+        fn csv_line(write: impl FnOnce(&mut csv::Writer<Vec<u8>>) -> csv::Result<()>) -> Result<String, io::Error> {
+            let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(vec![]);
+            write(&mut writer)?;
+            let bytes = writer.into_inner().map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+
+        impl ExportFormat {
+            fn header(&self) -> Option<Result<String, io::Error>> {
+                match self {
+                    // Sent before the first row, so that even an empty export has the header.
+                    ExportFormat::Csv => Some(csv_line(|writer| writer.write_record(CSV_HEADER))),
+                    ExportFormat::Jsonl => None,
+                    ExportFormat::Json => Some(Ok("[".to_string())),
+                }
+            }
+            fn row(&self, item: &MockItem, first: bool) -> Result<String, io::Error> {
+                match self {
+                    ExportFormat::Csv => csv_line(|writer| writer.serialize(item)),
+                    ExportFormat::Jsonl => Ok(format!("{}\n", serde_json::to_string(item)?)),
+                    ExportFormat::Json => Ok(format!("{}{}", if first { "" } else { "," }, serde_json::to_string(item)?)),
+                }
+            }
+            fn footer(&self) -> Option<String> {
+                match self {
+                    ExportFormat::Json => Some("]".to_string()),
+                    ExportFormat::Csv | ExportFormat::Jsonl => None,
+                }
+            }
+        }
+
+        /// Streams the items matching the list view's filter and sort as CSV, JSON Lines or a JSON
+        /// array, to anyone who may see the list view.
+        pub async fn export_items_handler(
+            Query(params): Query<ExportParams>,
+            headers: HeaderMap,
+        ) -> Result<impl IntoResponse, (StatusCode, String)> {
+            let user = user_for_headers(&headers)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            Permission::ReadItems
+                .check(user.as_ref())
+                .map_err(|e| (e.status_code(), e.to_string()))?;
+            let ExportParams { format, filter } = params;
+            let (tx, rx) = mpsc::channel::<Result<String, io::Error>>(EXPORT_BUFFER);
+
+            tokio::spawn(async move {
+                if let Some(header) = format.header() {
+                    let failed = header.is_err();
+                    if tx.send(header).await.is_err() || failed {
+                        return;
+                    }
+                }
+                let mut first = true;
+                for page in 1.. {
+                    let items = match repository().list(&filter, page, EXPORT_BATCH).await {
+                        Ok((items, _)) => items,
+                        Err(e) => {
+                            _ = tx.send(Err(io::Error::new(io::ErrorKind::Other, e))).await;
+                            return;
+                        }
+                    };
+                    let last_page = (items.len() as u32) < EXPORT_BATCH;
+                    for item in items {
+                        let chunk = format.row(&item, first);
+                        first = false;
+                        let failed = chunk.is_err();
+                        // the client went away, stop reading from the repository
+                        if tx.send(chunk).await.is_err() || failed {
+                            return;
+                        }
+                    }
+                    if last_page {
+                        break;
+                    }
+                }
+                if let Some(footer) = format.footer() {
+                    _ = tx.send(Ok(footer)).await;
+                }
+            });
+
+            let body = StreamBody::new(stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|chunk| (chunk, rx))
+            }));
+            Ok((
+                [
+                    (header::CONTENT_TYPE, format.content_type().to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"items.{}\"", format.extension()),
+                    ),
+                ],
+                body,
+            ))
+        }
+    }
+}
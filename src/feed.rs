@@ -0,0 +1,215 @@
+use cfg_if::cfg_if;
+
+pub const ATOM_FEED_PATH: &str = "/items/feed.atom";
+pub const RSS_FEED_PATH: &str = "/items/feed.rss";
+pub const DEFAULT_FEED_LIMIT: u32 = 20;
+pub const MAX_FEED_LIMIT: u32 = 100;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::fmt::Write;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        use axum::{
+            extract::{Query, State},
+            http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+            response::{IntoResponse, Response},
+            routing::get,
+            Router,
+        };
+        use serde::Deserialize;
+        use sha2::{Digest, Sha256};
+
+        use crate::file::etag_matches;
+        use crate::item_filter::{ItemFilter, ItemSort};
+        use crate::item_repository::{repository, FeedEntry, RepositoryError};
+        use crate::page_meta::SITE_NAME;
+        use crate::sitemap::{base_url, xml_escape};
+
+        /// Hex digits of the body hash in the `ETag`.
+        const ETAG_LEN: usize = 32;
+
+        /// Timestamp of the items added before `item_revisions` existed.
+        const UNKNOWN_TIMESTAMP: &str = "1970-01-01T00:00:00Z";
+
+        /// The list view's filter, except that feeds are sorted newest first unless asked otherwise.
+        #[derive(Debug, Default, Deserialize)]
+        #[serde(default)]
+        pub struct FeedParams {
+            q: Option<String>,
+            sort: Option<ItemSort>,
+            /// [DEFAULT_FEED_LIMIT] when not given, at most [MAX_FEED_LIMIT].
+            limit: Option<u32>,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        enum FeedFormat {
+            Atom,
+            Rss,
+        }
+
+        /// `public_url` like in `SitemapConfig`, the feeds link to the items with absolute URLs.
+        pub fn router(public_url: Option<String>) -> Router {
+            Router::new()
+                .route(ATOM_FEED_PATH, get(atom_feed_handler))
+                .route(RSS_FEED_PATH, get(rss_feed_handler))
+                .with_state(public_url)
+        }
+
+        async fn atom_feed_handler(
+            State(public_url): State<Option<String>>,
+            Query(params): Query<FeedParams>,
+            uri: Uri,
+            headers: HeaderMap,
+        ) -> Response {
+            feed(FeedFormat::Atom, public_url.as_deref(), params, uri, headers).await
+        }
+
+        async fn rss_feed_handler(
+            State(public_url): State<Option<String>>,
+            Query(params): Query<FeedParams>,
+            uri: Uri,
+            headers: HeaderMap,
+        ) -> Response {
+            feed(FeedFormat::Rss, public_url.as_deref(), params, uri, headers).await
+        }
+
+        /// The latest items matching `params`, answering `If-None-Match` and `If-Modified-Since`
+        /// with a 304 so that feed readers can poll cheaply.
+        async fn feed(
+            format: FeedFormat,
+            public_url: Option<&str>,
+            params: FeedParams,
+            uri: Uri,
+            headers: HeaderMap,
+        ) -> Response {
+            let filter = ItemFilter {
+                q: params.q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty()),
+                sort: params.sort.unwrap_or(ItemSort::IdDesc),
+            };
+            let limit = params.limit.unwrap_or(DEFAULT_FEED_LIMIT).clamp(1, MAX_FEED_LIMIT);
+            let feed = async {
+                let entries = repository().feed_entries(&filter, limit).await?;
+                let last_deletion = repository().last_deletion(&filter).await?;
+                Ok::<_, RepositoryError>((entries, last_deletion))
+            };
+            let (entries, last_deletion) = match feed.await {
+                Ok(feed) => feed,
+                Err(e) => {
+                    tracing::error!(error = %e, "couldn't build the item feed");
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            };
+
+            let base_url = base_url(public_url, &headers);
+            let title = match &filter.q {
+                Some(q) => format!("{SITE_NAME} items matching \"{q}\""),
+                None => format!("{SITE_NAME} items"),
+            };
+            let body = match format {
+                FeedFormat::Atom => atom(&base_url, &uri, &filter, &title, &entries),
+                FeedFormat::Rss => rss(&base_url, &filter, &title, &entries),
+            };
+
+            // The same across restarts and builds, unlike `DefaultHasher`.
+            let hash: String = Sha256::digest(body.as_bytes()).iter().map(|b| format!("{b:02x}")).collect();
+            let etag = HeaderValue::from_str(&format!("\"{}\"", &hash[..ETAG_LEN])).ok();
+            // Deleting an item changes the feed as well, it isn't among the entries anymore.
+            let last_modified = entries
+                .iter()
+                .filter_map(|entry| entry.updated_unix)
+                .chain(last_deletion)
+                .max()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64));
+
+            let not_modified = match (headers.get(header::IF_NONE_MATCH), headers.get(header::IF_MODIFIED_SINCE)) {
+                (Some(if_none_match), _) => etag.as_ref().map_or(false, |etag| etag_matches(etag, if_none_match)),
+                (None, Some(if_modified_since)) => {
+                    let since = if_modified_since.to_str().ok().and_then(|since| httpdate::parse_http_date(since).ok());
+                    matches!((last_modified, since), (Some(last_modified), Some(since)) if last_modified <= since)
+                }
+                (None, None) => false,
+            };
+            let mut res = if not_modified {
+                StatusCode::NOT_MODIFIED.into_response()
+            } else {
+                let content_type = match format {
+                    FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+                    FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+                };
+                ([(header::CONTENT_TYPE, content_type)], body).into_response()
+            };
+            let res_headers = res.headers_mut();
+            if let Some(etag) = etag {
+                res_headers.insert(header::ETAG, etag);
+            }
+            if let Some(last_modified) = last_modified.and_then(|time| HeaderValue::from_str(&httpdate::fmt_http_date(time)).ok()) {
+                res_headers.insert(header::LAST_MODIFIED, last_modified);
+            }
+            res
+        }
+
+        /// The items list with the feed's filter applied, the page the feed is the alternate of.
+        fn items_url(base_url: &str, filter: &ItemFilter) -> String {
+            match filter.to_query_string() {
+                query if query.is_empty() => format!("{base_url}/items"),
+                query => format!("{base_url}/items?{query}"),
+            }
+        }
+
+        fn atom(base_url: &str, uri: &Uri, filter: &ItemFilter, title: &str, entries: &[FeedEntry]) -> String {
+            let updated = entries
+                .iter()
+                .filter_map(|entry| entry.updated.as_deref())
+                .max()
+                .unwrap_or(UNKNOWN_TIMESTAMP);
+            let self_url = xml_escape(&format!("{base_url}{uri}"));
+            let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+            _ = writeln!(xml, "<title>{}</title>", xml_escape(title));
+            _ = writeln!(xml, "<id>{self_url}</id>");
+            _ = writeln!(xml, "<link rel=\"self\" href=\"{self_url}\"/>");
+            _ = writeln!(xml, "<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>", xml_escape(&items_url(base_url, filter)));
+            _ = writeln!(xml, "<updated>{updated}</updated>");
+            for entry in entries {
+                let url = xml_escape(&format!("{base_url}/items/{}", entry.id));
+                xml.push_str("<entry>\n");
+                _ = writeln!(xml, "<title>{}</title>", xml_escape(&entry.title));
+                _ = writeln!(xml, "<id>{url}</id>");
+                _ = writeln!(xml, "<link href=\"{url}\"/>");
+                if let Some(published) = &entry.published {
+                    _ = writeln!(xml, "<published>{published}</published>");
+                }
+                _ = writeln!(xml, "<updated>{}</updated>", entry.updated.as_deref().unwrap_or(UNKNOWN_TIMESTAMP));
+                _ = writeln!(xml, "<summary>{}</summary>", xml_escape(&entry.description));
+                xml.push_str("</entry>\n");
+            }
+            xml.push_str("</feed>\n");
+            xml
+        }
+
+        fn rss(base_url: &str, filter: &ItemFilter, title: &str, entries: &[FeedEntry]) -> String {
+            let rfc822 = |secs: i64| httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64));
+            let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+            _ = writeln!(xml, "<title>{}</title>", xml_escape(title));
+            _ = writeln!(xml, "<link>{}</link>", xml_escape(&items_url(base_url, filter)));
+            _ = writeln!(xml, "<description>{}</description>", xml_escape(title));
+            if let Some(updated) = entries.iter().filter_map(|entry| entry.updated_unix).max() {
+                _ = writeln!(xml, "<lastBuildDate>{}</lastBuildDate>", rfc822(updated));
+            }
+            for entry in entries {
+                let url = xml_escape(&format!("{base_url}/items/{}", entry.id));
+                xml.push_str("<item>\n");
+                _ = writeln!(xml, "<title>{}</title>", xml_escape(&entry.title));
+                _ = writeln!(xml, "<link>{url}</link>");
+                _ = writeln!(xml, "<guid isPermaLink=\"true\">{url}</guid>");
+                _ = writeln!(xml, "<description>{}</description>", xml_escape(&entry.description));
+                if let Some(published) = entry.published_unix {
+                    _ = writeln!(xml, "<pubDate>{}</pubDate>", rfc822(published));
+                }
+                xml.push_str("</item>\n");
+            }
+            xml.push_str("</channel>\n</rss>\n");
+            xml
+        }
+    }
+}
@@ -1,41 +1,242 @@
 use cfg_if::cfg_if;
 
+/// `Cache-Control` of static files, revalidated with their `ETag` afterwards.
+pub const SHORT_CACHE_CONTROL: &str = "public, max-age=300";
+/// `Cache-Control` of the JS and wasm files copied by [fingerprint_pkg], whose names change with
+/// their contents.
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
 cfg_if! {
 if #[cfg(feature = "ssr")] {
     use axum::{
-        body::{boxed, Body, BoxBody},
+        body::{boxed, Body, BoxBody, Empty},
         extract::Extension,
-        http::{Request, Response, StatusCode, Uri},
+        http::{header, HeaderMap, HeaderValue, Request, Response, StatusCode, Uri},
+        response::{IntoResponse, Response as AxumResponse},
     };
+    use std::io::{self, Write};
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use std::time::UNIX_EPOCH;
+    use sha2::{Digest, Sha256};
     use tower::ServiceExt;
     use tower_http::services::ServeDir;
     use std::sync::Arc;
-    use leptos::LeptosOptions;
+    use leptos::*;
+
+    use crate::error_template::{AppError, ErrorTemplate, ErrorTemplateProps};
+
+    /// Only text-like files are worth compressing, images and fonts already are compressed.
+    const COMPRESSIBLE_EXTENSIONS: [&str; 9] = ["css", "html", "ico", "js", "json", "svg", "txt", "wasm", "xml"];
+    const MIN_COMPRESS_BYTES: u64 = 1024;
+    /// Hex digits of the content hash in the names written by [fingerprint_pkg].
+    const FINGERPRINT_LEN: usize = 16;
+
+    /// URL paths of the files written by [fingerprint_pkg], served as immutable.
+    static FINGERPRINTED: OnceLock<Vec<String>> = OnceLock::new();
 
-    pub async fn file_handler(uri: Uri, Extension(options): Extension<Arc<LeptosOptions>>) -> Result<Response<BoxBody>, (StatusCode, String)> {
+    /// Copies the JS and wasm files of the app under `site_pkg_dir` to names carrying a hash of
+    /// their contents, `{output_name}-{hash}.js` and so on, and points `options.output_name` at the
+    /// copies, so that the pages load them and they can be cached for good. cargo-leptos 0.1
+    /// doesn't fingerprint them itself. Run it once at startup, before the app is served.
+    pub fn fingerprint_pkg(options: &mut LeptosOptions) -> io::Result<()> {
+        let pkg_dir = Path::new(&options.site_root).join(&options.site_pkg_dir);
+        // `leptos_axum` adds `_bg` to the name of the wasm file unless cargo-leptos set the name.
+        let wasm_suffix = if std::env::var("LEPTOS_OUTPUT_NAME").is_err() { "_bg.wasm" } else { ".wasm" };
+        let suffixes = [".js", wasm_suffix];
+
+        let mut hasher = Sha256::new();
+        let mut files = vec![];
+        for suffix in suffixes {
+            let contents = std::fs::read(pkg_dir.join(format!("{}{suffix}", options.output_name)))?;
+            hasher.update(&contents);
+            files.push((suffix, contents));
+        }
+        let hash: String = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+        let output_name = format!("{}-{}", options.output_name, &hash[..FINGERPRINT_LEN]);
+
+        let mut paths = vec![];
+        for (suffix, contents) in files {
+            let name = format!("{output_name}{suffix}");
+            let path = pkg_dir.join(&name);
+            if !path.exists() {
+                std::fs::write(&path, contents)?;
+            }
+            paths.push(format!("/{}/{name}", options.site_pkg_dir.trim_matches('/')));
+        }
+        if FINGERPRINTED.set(paths).is_err() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "the pkg files are already fingerprinted"));
+        }
+        options.output_name = output_name;
+        Ok(())
+    }
+
+    /// Whether the URL `path` names one of the files written by [fingerprint_pkg].
+    fn is_fingerprinted(path: &str) -> bool {
+        FINGERPRINTED
+            .get()
+            .map_or(false, |paths| paths.iter().any(|fingerprinted| fingerprinted == path))
+    }
+
+    /// Fallback of the router: serves static files from `site_root`, or renders [ErrorTemplate]
+    /// with a 404 when there is no such file and a 500 when it can't be read.
+    pub async fn file_and_error_handler(uri: Uri, Extension(options): Extension<Arc<LeptosOptions>>, req: Request<Body>) -> AxumResponse {
         let options = &*options;
         let root = options.site_root.clone();
-        let res = get_static_file(uri.clone(), &root).await?;
+        let error = match get_static_file(uri.clone(), req.headers(), &root).await {
+            Ok(res) if res.status() != StatusCode::NOT_FOUND => return res.into_response(),
+            Ok(_) => AppError::NotFound,
+            Err((_, message)) => AppError::Internal(message),
+        };
 
-        match res.status() {
-            StatusCode::OK => Ok(res),
-            _ => Err((res.status(), "File Not Found".to_string())),
-        }
+        let status = error.status_code();
+        let handler = leptos_axum::render_app_to_stream(options.to_owned(), move |cx| {
+            let mut errors = Errors::default();
+            errors.insert(Default::default(), error.clone());
+            view! {cx, <ErrorTemplate outside_errors=errors/>}
+        });
+        let mut res = handler(req).await.into_response();
+        *res.status_mut() = status;
+        res
     }
 
-    pub async fn get_static_file(uri: Uri, root: &str) -> Result<Response<BoxBody>, (StatusCode, String)> {
-        let req = Request::builder()
+    /// Serves `uri` from `root`, preferring the `.br`/`.gz` variants written by [precompress_dir]
+    /// when the client accepts them. Answers `If-Modified-Since` and `If-None-Match` with a 304.
+    async fn get_static_file(uri: Uri, headers: &HeaderMap, root: &str) -> Result<Response<BoxBody>, (StatusCode, String)> {
+        let mut req = Request::builder()
             .uri(uri.clone())
             .body(Body::empty())
             .unwrap();
-        let root_path = format!("{root}");
-        match ServeDir::new(&root_path).oneshot(req).await {
-            Ok(res) => Ok(res.map(boxed)),
-            Err(err) => Err((
+        // Only what `ServeDir` needs for content negotiation, conditional and range requests.
+        for name in [header::ACCEPT_ENCODING, header::IF_MODIFIED_SINCE, header::RANGE] {
+            if let Some(value) = headers.get(&name) {
+                req.headers_mut().insert(name, value.clone());
+            }
+        }
+        // `ServeDir` implements `tower::Service` so we can call it with `tower::ServiceExt::oneshot`
+        // This path is relative to the cargo root
+        let res = match ServeDir::new(root).precompressed_br().precompressed_gzip().oneshot(req).await {
+            Ok(res) => res.map(boxed),
+            Err(err) => return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Something went wrong: {}", err),
             ))
+        };
+        if !(res.status().is_success() || res.status() == StatusCode::NOT_MODIFIED) {
+            return Ok(res);
         }
+
+        // A partial response's length isn't the file's.
+        let etag = if res.status() == StatusCode::OK { etag(res.headers()) } else { None };
+        let not_modified = matches!(
+            (&etag, headers.get(header::IF_NONE_MATCH)),
+            (Some(etag), Some(if_none_match)) if etag_matches(etag, if_none_match)
+        );
+        let mut res = if not_modified {
+            Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .body(boxed(Empty::new()))
+                .unwrap()
+        } else {
+            res
+        };
+        let res_headers = res.headers_mut();
+        let cache_control = if is_fingerprinted(uri.path()) { IMMUTABLE_CACHE_CONTROL } else { SHORT_CACHE_CONTROL };
+        res_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control));
+        res_headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+        if let Some(etag) = etag {
+            res_headers.insert(header::ETAG, etag);
+        }
+        Ok(res)
+    }
+
+    /// Weak `ETag` of a file `ServeDir` answered with, from its `Last-Modified`, `Content-Length`
+    /// and `Content-Encoding`: the compressed variants of a file get tags of their own.
+    fn etag(headers: &HeaderMap) -> Option<HeaderValue> {
+        let modified = headers
+            .get(header::LAST_MODIFIED)
+            .and_then(|modified| modified.to_str().ok())
+            .and_then(|modified| httpdate::parse_http_date(modified).ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())?;
+        let len: u64 = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse().ok())?;
+        let encoding = headers
+            .get(header::CONTENT_ENCODING)
+            .and_then(|encoding| encoding.to_str().ok())
+            .map_or_else(String::new, |encoding| format!("-{encoding}"));
+        HeaderValue::from_str(&format!("W/\"{:x}-{len:x}{encoding}\"", modified.as_secs())).ok()
+    }
+
+    /// Weak comparison of `etag` with the tags listed in an `If-None-Match` header.
+    pub(crate) fn etag_matches(etag: &HeaderValue, if_none_match: &HeaderValue) -> bool {
+        let (Ok(etag), Ok(if_none_match)) = (etag.to_str(), if_none_match.to_str()) else {
+            return false;
+        };
+        let etag = etag.trim_start_matches("W/");
+        if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+    }
+
+    /// Writes `.br` and `.gz` variants next to every compressible file under `dir` that doesn't
+    /// have up to date ones yet. Blocking, run it with `spawn_blocking`.
+    pub fn precompress_dir(dir: &Path) -> io::Result<usize> {
+        let mut written = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                written += precompress_dir(&path)?;
+                continue;
+            }
+            let compressible = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| COMPRESSIBLE_EXTENSIONS.contains(&ext));
+            let metadata = path.metadata()?;
+            if !compressible || metadata.len() < MIN_COMPRESS_BYTES {
+                continue;
+            }
+
+            let mut contents = None;
+            for ext in ["br", "gz"] {
+                let mut variant = path.clone().into_os_string();
+                variant.push(".");
+                variant.push(ext);
+                let up_to_date = Path::new(&variant)
+                    .metadata()
+                    .and_then(|variant| Ok(variant.modified()? >= metadata.modified()?))
+                    .unwrap_or(false);
+                if up_to_date {
+                    continue;
+                }
+
+                if contents.is_none() {
+                    contents = Some(std::fs::read(&path)?);
+                }
+                let contents = contents.as_deref().unwrap();
+                let compressed = if ext == "br" { brotli_compress(contents)? } else { gzip_compress(contents)? };
+                std::fs::write(&variant, compressed)?;
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    fn brotli_compress(contents: &[u8]) -> io::Result<Vec<u8>> {
+        let mut compressed = vec![];
+        let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        writer.write_all(contents)?;
+        drop(writer);
+        Ok(compressed)
+    }
+
+    fn gzip_compress(contents: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+        encoder.write_all(contents)?;
+        encoder.finish()
     }
 
 }
@@ -0,0 +1,53 @@
+use cfg_if::cfg_if;
+
+pub const HEALTHZ_PATH: &str = "/healthz";
+pub const READYZ_PATH: &str = "/readyz";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use axum::{http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+        use serde::Serialize;
+
+        use crate::item_repository::repository;
+
+        pub fn router() -> Router {
+            Router::new()
+                .route(HEALTHZ_PATH, get(healthz))
+                .route(READYZ_PATH, get(readyz))
+        }
+
+        /// The process is up and serving requests.
+        async fn healthz() -> &'static str {
+            "ok"
+        }
+
+        #[derive(Debug, Serialize)]
+        struct Readiness {
+            /// The configured `Repository` couldn't be reached.
+            #[serde(skip_serializing_if = "Option::is_none")]
+            repository_error: Option<String>,
+            /// `{version} {description}` of the migrations embedded in the binary that the
+            /// repository hasn't applied.
+            missing_migrations: Vec<String>,
+        }
+
+        /// The repository answers and has every migration applied.
+        async fn readyz() -> impl IntoResponse {
+            let mut readiness = Readiness {
+                repository_error: None,
+                missing_migrations: vec![],
+            };
+            match repository().ping().await {
+                Ok(()) => match repository().pending_migrations().await {
+                    Ok(pending) => readiness.missing_migrations = pending,
+                    Err(e) => readiness.repository_error = Some(e.to_string()),
+                },
+                Err(e) => readiness.repository_error = Some(e.to_string()),
+            }
+
+            let ready = readiness.repository_error.is_none() && readiness.missing_migrations.is_empty();
+            let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+            (status, Json(readiness))
+        }
+    }
+}
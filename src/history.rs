@@ -0,0 +1,159 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthContext;
+use crate::authz::Permission;
+use crate::csrf::{CsrfActionForm, CsrfActionFormProps};
+use crate::items::UpdateItem;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::authz::{authorize_item, AuthError};
+        use crate::item_repository::repository;
+        use crate::item_store;
+
+        pub fn register_server_functions() {
+            _ = GetItemRevisions::register();
+            _ = RevertItem::register();
+        }
+    }
+}
+
+/// One row of the append-only `item_revisions` table, written by the triggers on `items`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct ItemRevision {
+    pub id: i64,
+    pub item_id: i64,
+    pub operation: String,
+    pub title: String,
+    pub description: String,
+    pub changed_at: String,
+    /// Username of who made the change, `None` for changes without a user (like purges) and
+    /// for users who have since been deleted.
+    pub changed_by: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: Option<String>,
+    pub new: String,
+}
+
+impl ItemRevision {
+    /// Field level changes of this revision compared to the revision before it.
+    pub fn diff(&self, previous: Option<&ItemRevision>) -> Vec<FieldChange> {
+        let fields = [
+            ("title", &self.title, previous.map(|p| &p.title)),
+            ("description", &self.description, previous.map(|p| &p.description)),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, new, old)| Some(*new) != *old)
+            .map(|(field, new, old)| FieldChange {
+                field,
+                old: old.cloned(),
+                new: new.clone(),
+            })
+            .collect()
+    }
+}
+
+#[server(GetItemRevisions, "/api")]
+pub async fn get_item_revisions(item_id: i64) -> Result<Vec<ItemRevision>, ServerFnError> {
+    repository()
+        .revisions(item_id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Items in the trash can't be reverted, they have to be restored first.
+#[server(RevertItem, "/api")]
+pub async fn revert_item(cx: Scope, revision_id: i64) -> Result<(), ServerFnError> {
+    let item_id = repository()
+        .revision_item_id(revision_id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or(AuthError::NotFound)?;
+    let changed_by = authorize_item(cx, item_id).await?.map(|user| user.id);
+
+    item_store::revert(revision_id, changed_by)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or(AuthError::NotFound)?;
+    Ok(())
+}
+
+#[component]
+pub fn ItemHistory(
+    cx: Scope,
+    item_id: i64,
+    owner_id: Option<i64>,
+    update_item: Action<UpdateItem, Result<(), ServerFnError>>,
+    revert_item: Action<RevertItem, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let auth = use_context::<AuthContext>(cx).unwrap();
+    let can_revert = Signal::derive(cx, move || {
+        Permission::ModifyItem { owner_id }.allows(auth.current_user().as_ref())
+    });
+    let revisions = create_resource(
+        cx,
+        move || (update_item.version().get(), revert_item.version().get()),
+        move |_| async move { get_item_revisions(item_id).await },
+    );
+
+    view! { cx,
+        <div>
+            <h2>"History"</h2>
+            <Transition fallback=move || view! {cx, <p>"Loading..."</p> }>
+                {move || revisions.read().map(|revisions| match revisions {
+                    Ok(revisions) => revisions
+                        .iter()
+                        .enumerate()
+                        .map(|(i, revision)| {
+                            let changes = revision.diff(revisions.get(i + 1));
+                            view! { cx, <ItemRevisionView revision=revision.clone() changes=changes revert_item=revert_item can_revert=can_revert/> }
+                        })
+                        .collect::<Vec<_>>()
+                        .into_view(cx),
+                    Err(e) => view! { cx, <pre class="error">"Server Error: " {e.to_string()}</pre>}.into_view(cx),
+                })}
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn ItemRevisionView(
+    cx: Scope,
+    revision: ItemRevision,
+    changes: Vec<FieldChange>,
+    revert_item: Action<RevertItem, Result<(), ServerFnError>>,
+    can_revert: Signal<bool>,
+) -> impl IntoView {
+    view! { cx,
+        <div class="revision">
+            <h4>
+                {format!("#{} {} at {}", revision.id, revision.operation, revision.changed_at)}
+                {revision.changed_by.clone().map(|username| format!(" by {username}"))}
+            </h4>
+            <ul>
+                {changes
+                    .into_iter()
+                    .map(|change| match change.old {
+                        Some(old) => view! { cx, <li>{change.field}": " <del>{old}</del>" → " <ins>{change.new}</ins></li> },
+                        None => view! { cx, <li>{change.field}": " <ins>{change.new}</ins></li> },
+                    })
+                    .collect::<Vec<_>>()}
+            </ul>
+            {move || can_revert().then(|| view! { cx,
+                <CsrfActionForm action=revert_item>
+                    <input type="hidden" name="revision_id" value={revision.id}/>
+                    <input type="submit" value="revert to this revision"/>
+                </CsrfActionForm>
+            })}
+        </div>
+    }
+}
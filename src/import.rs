@@ -0,0 +1,292 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthContext;
+
+pub const IMPORT_ITEMS_PATH: &str = "/import/items";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    #[default]
+    Csv,
+    Json,
+    Jsonl,
+}
+
+impl ImportFormat {
+    /// Guesses the format from a file name, defaults to CSV.
+    pub fn from_file_name(name: &str) -> Self {
+        match name.rsplit('.').next().map(str::to_lowercase).as_deref() {
+            Some("json") => ImportFormat::Json,
+            Some("jsonl") | Some("ndjson") => ImportFormat::Jsonl,
+            _ => ImportFormat::Csv,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "csv",
+            ImportFormat::Json => "json",
+            ImportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// A single invalid row aborts the whole import.
+    #[default]
+    AllOrNothing,
+    /// Invalid rows are skipped, the valid ones are imported.
+    SkipInvalid,
+}
+
+impl ImportMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportMode::AllOrNothing => "all_or_nothing",
+            ImportMode::SkipInvalid => "skip_invalid",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportParams {
+    pub format: ImportFormat,
+    pub mode: ImportMode,
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportRowError {
+    /// 1-based row number in the uploaded file, not counting the CSV header.
+    pub row: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub inserted: usize,
+    /// Invalid rows left out in [ImportMode::SkipInvalid].
+    pub skipped: usize,
+    /// Invalid rows that stopped an [ImportMode::AllOrNothing] import.
+    pub failed: usize,
+    /// Valid rows not imported because other rows failed, in [ImportMode::AllOrNothing].
+    pub not_imported: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use axum::{
+            body::Bytes,
+            extract::Query,
+            http::{HeaderMap, StatusCode},
+            Json,
+        };
+        use crate::auth::user_for_headers;
+        use crate::authz::Permission;
+        use crate::item_store;
+        use crate::items::validate_item;
+
+        #[derive(Debug, Default, Deserialize)]
+        #[serde(default)]
+        struct ImportRow {
+            title: String,
+            description: String,
+        }
+
+        fn parse_rows(format: ImportFormat, body: &[u8]) -> Result<Vec<Result<ImportRow, String>>, String> {
+            match format {
+                ImportFormat::Csv => Ok(csv::Reader::from_reader(body)
+                    .deserialize()
+                    .map(|row| row.map_err(|e| e.to_string()))
+                    .collect()),
+                ImportFormat::Json => serde_json::from_slice::<Vec<serde_json::Value>>(body)
+                    .map(|rows| {
+                        rows.into_iter()
+                            .map(|row| serde_json::from_value(row).map_err(|e| e.to_string()))
+                            .collect()
+                    })
+                    .map_err(|e| e.to_string()),
+                ImportFormat::Jsonl => std::str::from_utf8(body)
+                    .map(|body| {
+                        body.lines()
+                            .filter(|line| !line.trim().is_empty())
+                            .map(|line| serde_json::from_str(line).map_err(|e| e.to_string()))
+                            .collect()
+                    })
+                    .map_err(|e| e.to_string()),
+            }
+        }
+
+        /// Validates the uploaded items with the rules of `AddItem` and, unless `dry_run` is set,
+        /// inserts the valid ones through `item_store` in a single transaction, owned by the
+        /// importing user.
+        pub async fn import_items_handler(
+            Query(params): Query<ImportParams>,
+            headers: HeaderMap,
+            body: Bytes,
+        ) -> Result<Json<ImportReport>, (StatusCode, String)> {
+            let user = user_for_headers(&headers)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            Permission::CreateItem
+                .check(user.as_ref())
+                .map_err(|e| (e.status_code(), e.to_string()))?;
+            let owner_id = user.map(|user| user.id);
+            let rows = parse_rows(params.format, &body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+            let mut report = ImportReport {
+                dry_run: params.dry_run,
+                ..ImportReport::default()
+            };
+            let mut valid = Vec::new();
+            for (i, row) in rows.into_iter().enumerate() {
+                let row_no = i + 1;
+                match row.map_err(|e| vec![e]).and_then(|row| {
+                    validate_item(&row.title, &row.description).map(|_| row)
+                }) {
+                    Ok(row) => valid.push((row_no, row)),
+                    Err(errors) => report.errors.push(ImportRowError { row: row_no, errors }),
+                }
+            }
+
+            let invalid = report.errors.len();
+            if params.mode == ImportMode::AllOrNothing && invalid > 0 {
+                report.failed = invalid;
+                report.not_imported = valid.len();
+                return Ok(Json(report));
+            }
+            report.skipped = invalid;
+            if params.dry_run {
+                report.inserted = valid.len();
+                return Ok(Json(report));
+            }
+
+            // In both modes the rows that get inserted go in one transaction, a storage error
+            // leaves the items as they were.
+            let items = valid.into_iter().map(|(_, row)| (row.title, row.description)).collect();
+            let inserted = item_store::insert_all(items, owner_id)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            report.inserted = inserted.len();
+            Ok(Json(report))
+        }
+    } else {
+        use gloo_net::http::Request;
+
+        use crate::csrf::CSRF_HEADER;
+
+        async fn upload(
+            file: web_sys::File,
+            mode: ImportMode,
+            dry_run: bool,
+            csrf_token: Option<String>,
+        ) -> Result<ImportReport, String> {
+            let format = ImportFormat::from_file_name(&file.name());
+            let url = format!(
+                "{IMPORT_ITEMS_PATH}?format={}&mode={}&dry_run={dry_run}",
+                format.as_str(),
+                mode.as_str()
+            );
+            let res = Request::post(&url)
+                .header(CSRF_HEADER, &csrf_token.unwrap_or_default())
+                .body(file)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if res.ok() {
+                res.json().await.map_err(|e| e.to_string())
+            } else {
+                Err(res.text().await.unwrap_or_else(|_| res.status_text()))
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ImportItems(cx: Scope) -> impl IntoView {
+    let (file, set_file) = create_signal(cx, None::<web_sys::File>);
+    let (mode, set_mode) = create_signal(cx, ImportMode::default());
+    let (dry_run, set_dry_run) = create_signal(cx, true);
+    let auth = use_context::<AuthContext>(cx).unwrap();
+
+    let import = create_action(cx, move |(file, mode, dry_run): &(web_sys::File, ImportMode, bool)| {
+        let (file, mode, dry_run) = (file.clone(), *mode, *dry_run);
+        let csrf_token = auth.csrf_token.read().flatten();
+        async move {
+            cfg_if! {
+                if #[cfg(feature = "ssr")] {
+                    _ = (file, mode, dry_run, csrf_token);
+                    Err::<ImportReport, String>("import is only available in the browser".to_string())
+                } else {
+                    upload(file, mode, dry_run, csrf_token).await
+                }
+            }
+        }
+    });
+
+    let on_file = move |e: web_sys::Event| {
+        let input = event_target::<web_sys::HtmlInputElement>(&e);
+        set_file(input.files().and_then(|files| files.get(0)));
+    };
+    let on_submit = move |_| {
+        if let Some(file) = file() {
+            import.dispatch((file, mode(), dry_run()));
+        }
+    };
+
+    view! { cx,
+        <div>
+            <h3>"Import Items"</h3>
+            <input type="file" accept=".csv,.json,.jsonl,.ndjson" on:change=on_file/>
+            <select on:change=move |e| set_mode(if event_target_value(&e) == ImportMode::SkipInvalid.as_str() {
+                ImportMode::SkipInvalid
+            } else {
+                ImportMode::AllOrNothing
+            })>
+                <option value=ImportMode::AllOrNothing.as_str() selected=move || mode() == ImportMode::AllOrNothing>"all or nothing"</option>
+                <option value=ImportMode::SkipInvalid.as_str() selected=move || mode() == ImportMode::SkipInvalid>"skip invalid rows"</option>
+            </select>
+            <label>
+                <input type="checkbox" prop:checked=dry_run on:change=move |e| set_dry_run(event_target_checked(&e))/>
+                "dry run"
+            </label>
+            <button disabled=move || file().is_none() || import.pending().get() on:click=on_submit>"Import"</button>
+            {move || import.value().get().map(|res| match res {
+                Ok(report) => view! { cx, <ImportReportView report=report/> }.into_view(cx),
+                Err(e) => view! { cx, <p class="error">"Import failed: " {e}</p> }.into_view(cx),
+            })}
+        </div>
+    }
+}
+
+#[component]
+fn ImportReportView(cx: Scope, report: ImportReport) -> impl IntoView {
+    view! { cx,
+        <div>
+            <p>{format!(
+                "{}{} inserted, {} skipped, {} failed",
+                if report.dry_run { "dry run: " } else { "" },
+                report.inserted,
+                report.skipped,
+                report.failed
+            )}</p>
+            {(report.not_imported > 0).then(|| view! { cx,
+                <p>{format!("{} valid rows not imported because of the failed rows", report.not_imported)}</p>
+            })}
+            <ul>
+                {report
+                    .errors
+                    .into_iter()
+                    .map(|e| view! { cx, <li class="error">{format!("row {}: {}", e.row, e.errors.join(", "))}</li> })
+                    .collect::<Vec<_>>()}
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,91 @@
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+use crate::items::MockItem;
+
+pub const ITEM_EVENTS_PATH: &str = "/events/items";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemEvent {
+    Added(MockItem),
+    Updated(MockItem),
+    Removed(i64),
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::convert::Infallible;
+        use std::sync::OnceLock;
+
+        use axum::response::sse::{Event, KeepAlive, Sse};
+        use futures::stream::{self, Stream};
+        use tokio::sync::broadcast::{self, error::RecvError};
+
+        const CHANNEL_CAPACITY: usize = 64;
+
+        fn sender() -> &'static broadcast::Sender<ItemEvent> {
+            static SENDER: OnceLock<broadcast::Sender<ItemEvent>> = OnceLock::new();
+            SENDER.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+        }
+
+        pub fn publish(event: ItemEvent) {
+            // no open subscribers is not an error, nobody is listening
+            _ = sender().send(event);
+        }
+
+        pub fn subscribe() -> broadcast::Receiver<ItemEvent> {
+            sender().subscribe()
+        }
+
+        pub async fn item_events_handler() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+            let events = stream::unfold(subscribe(), |mut rx| async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(event) => match Event::default().json_data(&event) {
+                            Ok(sse_event) => return Some((Ok(sse_event), rx)),
+                            Err(e) => log::error!("Error serializing item event: {e}"),
+                        },
+                        Err(RecvError::Lagged(n)) => log::warn!("Item event subscriber lagged by {n} events"),
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            });
+            Sse::new(events).keep_alive(KeepAlive::default())
+        }
+    } else {
+        use futures::StreamExt;
+        use gloo_net::eventsource::futures::EventSource;
+        use leptos::{on_cleanup, spawn_local, Scope};
+
+        /// Subscribes to the server's item event stream and calls `on_event` for every event
+        /// until the scope is disposed.
+        pub fn subscribe_item_events(cx: Scope, on_event: impl Fn(ItemEvent) + 'static) {
+            let mut event_source = match EventSource::new(ITEM_EVENTS_PATH) {
+                Ok(es) => es,
+                Err(e) => {
+                    log::error!("Error opening item event stream: {e}");
+                    return;
+                }
+            };
+            let mut messages = match event_source.subscribe("message") {
+                Ok(messages) => messages,
+                Err(e) => {
+                    log::error!("Error subscribing to item events: {e}");
+                    return;
+                }
+            };
+            spawn_local(async move {
+                while let Some(Ok((_, msg))) = messages.next().await {
+                    let Some(data) = msg.data().as_string() else {
+                        continue;
+                    };
+                    match serde_json::from_str(&data) {
+                        Ok(event) => on_event(event),
+                        Err(e) => log::error!("Error reading item event: {e}"),
+                    }
+                }
+            });
+            on_cleanup(cx, move || event_source.close());
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use cfg_if::cfg_if;
+use leptos::form_urlencoded;
+use leptos_router::ParamsMap;
+use serde::{Deserialize, Serialize};
+
+use crate::items::MockItem;
+
+pub const SEARCH_QUERY_PARAM: &str = "q";
+pub const SORT_QUERY_PARAM: &str = "sort";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub enum ItemSort {
+    #[default]
+    #[serde(rename = "id")]
+    IdAsc,
+    #[serde(rename = "-id")]
+    IdDesc,
+    #[serde(rename = "title")]
+    TitleAsc,
+    #[serde(rename = "-title")]
+    TitleDesc,
+}
+
+impl ItemSort {
+    pub const ALL: [ItemSort; 4] = [
+        ItemSort::IdAsc,
+        ItemSort::IdDesc,
+        ItemSort::TitleAsc,
+        ItemSort::TitleDesc,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemSort::IdAsc => "id",
+            ItemSort::IdDesc => "-id",
+            ItemSort::TitleAsc => "title",
+            ItemSort::TitleDesc => "-title",
+        }
+    }
+    pub fn parse(s: &str) -> Option<Self> {
+        ItemSort::ALL.into_iter().find(|sort| sort.as_str() == s)
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            ItemSort::IdAsc => "oldest first",
+            ItemSort::IdDesc => "newest first",
+            ItemSort::TitleAsc => "title A-Z",
+            ItemSort::TitleDesc => "title Z-A",
+        }
+    }
+}
+
+/// The search and sort parameters of the items list, as carried in the `ItemsView` URL.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItemFilter {
+    pub q: Option<String>,
+    pub sort: ItemSort,
+}
+
+impl ItemFilter {
+    /// Whether `item` is found by the search, case insensitively in its title or description.
+    pub fn matches(&self, item: &MockItem) -> bool {
+        self.q.as_deref().map_or(true, |q| {
+            let q = q.to_lowercase();
+            item.title.to_lowercase().contains(&q) || item.description.to_lowercase().contains(&q)
+        })
+    }
+
+    pub fn from_query_map(query_map: &ParamsMap) -> Self {
+        ItemFilter {
+            q: query_map
+                .get(SEARCH_QUERY_PARAM)
+                .map(|q| q.trim().to_string())
+                .filter(|q| !q.is_empty()),
+            sort: query_map
+                .get(SORT_QUERY_PARAM)
+                .and_then(|s| ItemSort::parse(s))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Url encoded query string of the filter, without the leading `?`.
+    pub fn to_query_string(&self) -> String {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        self.append_query_pairs(&mut serializer);
+        serializer.finish()
+    }
+
+    /// Query string of the items list at `page` with this filter applied.
+    pub fn to_page_query_string(&self, page: usize, page_size: usize) -> String {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        serializer
+            .append_pair("page", &page.to_string())
+            .append_pair("page_size", &page_size.to_string());
+        self.append_query_pairs(&mut serializer);
+        serializer.finish()
+    }
+
+    fn append_query_pairs(&self, serializer: &mut form_urlencoded::Serializer<String>) {
+        if let Some(q) = &self.q {
+            serializer.append_pair(SEARCH_QUERY_PARAM, q);
+        }
+        if self.sort != ItemSort::default() {
+            serializer.append_pair(SORT_QUERY_PARAM, self.sort.as_str());
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use sqlx::{QueryBuilder, Sqlite};
+
+        impl ItemFilter {
+            /// Pushes the `WHERE` clause selecting the (not deleted) items matching this filter.
+            pub fn push_where(&self, qb: &mut QueryBuilder<'_, Sqlite>) {
+                qb.push(" WHERE deleted_at IS NULL");
+                self.push_matches(qb);
+            }
+
+            /// The condition on `q`, appended to a `WHERE` clause with `AND`.
+            pub fn push_matches(&self, qb: &mut QueryBuilder<'_, Sqlite>) {
+                if let Some(q) = &self.q {
+                    let pattern = format!(
+                        "%{}%",
+                        q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+                    );
+                    qb.push(" AND (title LIKE ")
+                        .push_bind(pattern.clone())
+                        .push(" ESCAPE '\\' OR description LIKE ")
+                        .push_bind(pattern)
+                        .push(" ESCAPE '\\')");
+                }
+            }
+
+            pub fn push_order_by(&self, qb: &mut QueryBuilder<'_, Sqlite>) {
+                qb.push(match self.sort {
+                    ItemSort::IdAsc => " ORDER BY id ASC",
+                    ItemSort::IdDesc => " ORDER BY id DESC",
+                    ItemSort::TitleAsc => " ORDER BY title ASC, id ASC",
+                    ItemSort::TitleDesc => " ORDER BY title DESC, id ASC",
+                });
+            }
+
+            /// `SELECT id, title, description, owner_id FROM items` with this filter and sort applied.
+            pub fn select_items(&self) -> QueryBuilder<'static, Sqlite> {
+                let mut qb = QueryBuilder::new("SELECT id, title, description, owner_id FROM items");
+                self.push_where(&mut qb);
+                self.push_order_by(&mut qb);
+                qb
+            }
+
+            pub fn count_items(&self) -> QueryBuilder<'static, Sqlite> {
+                let mut qb = QueryBuilder::new("SELECT COUNT(*) FROM items");
+                self.push_where(&mut qb);
+                qb
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+pub mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "ssr")]
+pub mod sqlite;
+
+use std::fmt;
+
+use async_trait::async_trait;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::User;
+use crate::dashboard::ItemStats;
+use crate::history::ItemRevision;
+use crate::item_filter::ItemFilter;
+use crate::items::MockItem;
+
+/// Storage of `items`, their trash and their history, the backend is picked at startup with
+/// [RepositoryConfig]. Implementations only store, publishing `ItemEvent`s is left to `item_store`
+/// and the server functions.
+#[async_trait]
+pub trait ItemRepository: Send + Sync {
+    /// One page of the (not deleted) items matching `filter` and the total number of matches.
+    async fn list(
+        &self,
+        filter: &ItemFilter,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError>;
+
+    /// Ids of all (not deleted) items matching `filter`, in its order.
+    async fn matching_ids(&self, filter: &ItemFilter) -> Result<Vec<i64>, RepositoryError>;
+
+    async fn get(&self, id: i64) -> Result<Option<MockItem>, RepositoryError>;
+
+    /// The (not deleted) items of `ids` that exist, in the order of `ids`.
+    async fn get_many(&self, ids: &[i64]) -> Result<Vec<MockItem>, RepositoryError>;
+
+    /// The owner of an item, deleted or not. `None` if there is no such item.
+    async fn owner_id(&self, id: i64) -> Result<Option<Option<i64>>, RepositoryError>;
+
+    /// Adds an item, changed by its owner as far as the history is concerned.
+    async fn insert(
+        &self,
+        title: String,
+        description: String,
+        owner_id: Option<i64>,
+    ) -> Result<MockItem, RepositoryError>;
+
+    /// Adds `(title, description)` items owned by `owner_id`, all of them or, if one fails, none.
+    async fn insert_all(
+        &self,
+        items: Vec<(String, String)>,
+        owner_id: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError>;
+
+    /// Updates the given fields of a not deleted item, `None` leaves a field as it is.
+    /// `changed_by` is the acting user, recorded in the item's history.
+    async fn update(
+        &self,
+        id: i64,
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Option<MockItem>, RepositoryError>;
+
+    /// [ItemRepository::update] of each of `ids`, all in one transaction. Returns the updated
+    /// items, those that weren't found (or are deleted) are left out.
+    async fn update_all(
+        &self,
+        ids: &[i64],
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError>;
+
+    /// Moves an item to the trash, returns `false` if there was no such (not deleted) item.
+    async fn soft_delete(&self, id: i64, changed_by: Option<i64>) -> Result<bool, RepositoryError>;
+
+    /// Moves each of `ids` to the trash in one transaction, returns the ids of those that were
+    /// found and not deleted yet.
+    async fn soft_delete_all(&self, ids: &[i64], changed_by: Option<i64>) -> Result<Vec<i64>, RepositoryError>;
+
+    /// One page of the items in the trash, most recently deleted first, and their total number.
+    /// Only those owned by `owner_id` unless it is `None`.
+    async fn list_deleted(
+        &self,
+        owner_id: Option<i64>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError>;
+
+    /// Takes an item out of the trash, `None` if there was no such deleted item.
+    async fn restore(&self, id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError>;
+
+    /// Permanently deletes the items in the trash for longer than `after_days`, returns how many.
+    async fn purge_deleted(&self, after_days: u32) -> Result<u64, RepositoryError>;
+
+    /// Every revision of an item, the latest first.
+    async fn revisions(&self, item_id: i64) -> Result<Vec<ItemRevision>, RepositoryError>;
+
+    /// The item a revision belongs to, `None` if there is no such revision.
+    async fn revision_item_id(&self, revision_id: i64) -> Result<Option<i64>, RepositoryError>;
+
+    /// Sets the title and description of a not deleted item back to those of one of its revisions.
+    /// `None` if there is no such revision or its item is in the trash.
+    async fn revert(&self, revision_id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError>;
+
+    /// The dashboard's numbers, over the last `days` days and with `recent` items per list.
+    async fn item_stats(&self, days: u32, recent: u32) -> Result<ItemStats, RepositoryError>;
+
+    /// The first `limit` (not deleted) items matching `filter`, with the times of their first
+    /// and last revision.
+    async fn feed_entries(&self, filter: &ItemFilter, limit: u32) -> Result<Vec<FeedEntry>, RepositoryError>;
+
+    /// When the last item matching `filter` that is still in the trash was deleted, in Unix
+    /// seconds. Feeds only list the items that are left, but have changed then too.
+    async fn last_deletion(&self, filter: &ItemFilter) -> Result<Option<i64>, RepositoryError>;
+
+    /// `limit` (not deleted) items from `offset` on, in id order, with the time of their last revision.
+    async fn last_modified(&self, offset: u32, limit: u32) -> Result<Vec<ItemLastModified>, RepositoryError>;
+
+    /// Brings the backend's schema up to date, at startup.
+    async fn migrate(&self) -> Result<(), RepositoryError>;
+
+    /// `{version} {description}` of the migrations the backend hasn't applied yet, for the readiness probe.
+    async fn pending_migrations(&self) -> Result<Vec<String>, RepositoryError>;
+
+    /// Checks that the backend can be reached, for the readiness probe.
+    async fn ping(&self) -> Result<(), RepositoryError>;
+}
+
+/// Storage of the users and their sessions. It lives with the items, which refer to their owners
+/// and whose revisions name who made them.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Adds a user with the default role, `None` if the username is already taken.
+    async fn create_user(&self, username: String, password_hash: String) -> Result<Option<User>, RepositoryError>;
+
+    /// A user and their password hash.
+    async fn user_by_name(&self, username: &str) -> Result<Option<(User, String)>, RepositoryError>;
+
+    /// Starts a session of `user_id` lasting `days` and removes the expired ones, logins being
+    /// when sessions pile up.
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        csrf_token: &str,
+        days: u32,
+    ) -> Result<(), RepositoryError>;
+
+    /// The user of a valid, not expired session.
+    async fn session_user(&self, token: &str) -> Result<Option<User>, RepositoryError>;
+
+    /// The CSRF token of a valid, not expired session.
+    async fn session_csrf_token(&self, token: &str) -> Result<Option<String>, RepositoryError>;
+
+    async fn delete_session(&self, token: &str) -> Result<(), RepositoryError>;
+}
+
+/// Everything the server stores, one backend keeps both.
+pub trait Repository: ItemRepository + UserRepository {}
+
+impl<T: ItemRepository + UserRepository> Repository for T {}
+
+/// An item in a feed. Timestamps are those of the item's first and last revision, `None` for
+/// items older than `item_revisions`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct FeedEntry {
+    pub id: i64,
+    pub title: String,
+    pub description: String,
+    /// RFC 3339, in UTC.
+    pub published: Option<String>,
+    pub updated: Option<String>,
+    pub published_unix: Option<i64>,
+    pub updated_unix: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct ItemLastModified {
+    pub id: i64,
+    /// When the item was last added, edited, restored or reverted, `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub lastmod: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepositoryError(pub String);
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "storage error: {}", self.0)
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::sync::{Arc, OnceLock};
+
+        use self::memory::InMemoryRepository;
+        use self::sqlite::SqliteRepository;
+
+        impl From<sqlx::Error> for RepositoryError {
+            fn from(e: sqlx::Error) -> Self {
+                RepositoryError(e.to_string())
+            }
+        }
+
+        /// The storage backend, chosen at startup.
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        pub enum RepositoryConfig {
+            /// Items.sqlite, see `items::pool`.
+            #[default]
+            Sqlite,
+            /// Nothing is kept across restarts.
+            Memory,
+            /// Needs the `postgres` feature.
+            Postgres { url: String },
+        }
+
+        impl RepositoryConfig {
+            /// `ITEM_BACKEND`: `sqlite` (the default), `memory`, or `postgres` with `POSTGRES_URL`.
+            pub fn from_env() -> Result<Self, String> {
+                match std::env::var("ITEM_BACKEND").as_deref() {
+                    Err(_) | Ok("sqlite") => Ok(RepositoryConfig::Sqlite),
+                    Ok("memory") => Ok(RepositoryConfig::Memory),
+                    Ok("postgres") => std::env::var("POSTGRES_URL")
+                        .map(|url| RepositoryConfig::Postgres { url })
+                        .map_err(|_| "ITEM_BACKEND=postgres needs POSTGRES_URL".to_string()),
+                    Ok(backend) => Err(format!("unknown ITEM_BACKEND {backend:?}")),
+                }
+            }
+
+            /// The configured backend, connecting lazily on first use.
+            pub fn repository(self) -> Result<Arc<dyn Repository>, String> {
+                match self {
+                    RepositoryConfig::Sqlite => Ok(Arc::new(SqliteRepository::new(crate::items::pool().clone()))),
+                    RepositoryConfig::Memory => Ok(Arc::new(InMemoryRepository::default())),
+                    #[cfg(feature = "postgres")]
+                    RepositoryConfig::Postgres { url } => Ok(Arc::new(postgres::PostgresRepository::connect_lazy(&url)?)),
+                    #[cfg(not(feature = "postgres"))]
+                    RepositoryConfig::Postgres { .. } => {
+                        Err("the postgres backend needs a build with the `postgres` feature".to_string())
+                    }
+                }
+            }
+        }
+
+        static REPOSITORY: OnceLock<Arc<dyn Repository>> = OnceLock::new();
+
+        /// Sets the backend of the whole server, fails if one is already in use.
+        pub fn set_repository(repository: Arc<dyn Repository>) -> Result<(), Arc<dyn Repository>> {
+            REPOSITORY.set(repository)
+        }
+
+        /// The backend set with [set_repository], SQLite if none was set before first use.
+        pub fn repository() -> &'static dyn Repository {
+            REPOSITORY
+                .get_or_init(|| Arc::new(SqliteRepository::new(crate::items::pool().clone())))
+                .as_ref()
+        }
+    }
+}
@@ -0,0 +1,594 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+
+use super::{FeedEntry, ItemLastModified, ItemRepository, RepositoryError, UserRepository};
+use crate::auth::{Role, User};
+use crate::dashboard::{DayCount, ItemActivity, ItemStats};
+use crate::history::ItemRevision;
+use crate::item_filter::{ItemFilter, ItemSort};
+use crate::items::MockItem;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Everything kept in memory, for tests and for running without a database. Builds without `ssr`
+/// too, for the `csr` demo. Keeps a history like the triggers of the SQL backends do.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    last_item_id: i64,
+    items: BTreeMap<i64, StoredItem>,
+    last_revision_id: i64,
+    revisions: Vec<Revision>,
+    last_user_id: i64,
+    /// Users by id, with their password hash.
+    users: BTreeMap<i64, (User, String)>,
+    sessions: HashMap<String, Session>,
+}
+
+struct StoredItem {
+    item: MockItem,
+    /// Unix time the item was moved to the trash.
+    deleted_at: Option<i64>,
+}
+
+struct Revision {
+    id: i64,
+    item_id: i64,
+    operation: &'static str,
+    title: String,
+    description: String,
+    changed_at: i64,
+    changed_by: Option<i64>,
+}
+
+struct Session {
+    user_id: i64,
+    csrf_token: String,
+    expires_at: i64,
+}
+
+impl InMemoryRepository {
+    /// A repository holding `items`, as if they had been added before revisions were recorded.
+    pub fn with_items(items: impl IntoIterator<Item = MockItem>) -> Self {
+        let items: BTreeMap<_, _> = items
+            .into_iter()
+            .map(|item| (item.id, StoredItem { item, deleted_at: None }))
+            .collect();
+        InMemoryRepository {
+            state: Mutex::new(State {
+                last_item_id: items.keys().last().copied().unwrap_or_default(),
+                items,
+                ..State::default()
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panic while holding the lock leaves the state as consistent as any single operation.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn now() -> i64 {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            (leptos::js_sys::Date::now() / 1000.0) as i64
+        } else {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64)
+        }
+    }
+}
+
+/// Year, month and day of the `days`th day since 1970-01-01, see
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `YYYY-MM-DD`, in UTC.
+fn date(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(SECONDS_PER_DAY));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC, the format of SQLite's `CURRENT_TIMESTAMP`.
+fn datetime(secs: i64) -> String {
+    let time = secs.rem_euclid(SECONDS_PER_DAY);
+    format!("{} {:02}:{:02}:{:02}", date(secs), time / 3600, time / 60 % 60, time % 60)
+}
+
+/// RFC 3339, in UTC.
+fn rfc3339(secs: i64) -> String {
+    datetime(secs).replacen(' ', "T", 1) + "Z"
+}
+
+/// Case-insensitive substring match, like SQLite's `LIKE '%q%'` on ASCII text.
+impl State {
+    /// The not deleted items matching `filter`, in its order.
+    fn matching(&self, filter: &ItemFilter) -> Vec<MockItem> {
+        let mut items: Vec<MockItem> = self
+            .items
+            .values()
+            .filter(|stored| stored.deleted_at.is_none())
+            .filter(|stored| filter.matches(&stored.item))
+            .map(|stored| stored.item.clone())
+            .collect();
+        match filter.sort {
+            ItemSort::IdAsc => {}
+            ItemSort::IdDesc => items.reverse(),
+            ItemSort::TitleAsc => items.sort_by(|a, b| a.title.cmp(&b.title).then(a.id.cmp(&b.id))),
+            ItemSort::TitleDesc => items.sort_by(|a, b| b.title.cmp(&a.title).then(a.id.cmp(&b.id))),
+        }
+        items
+    }
+
+    fn live(&self, id: i64) -> Option<&MockItem> {
+        self.items
+            .get(&id)
+            .filter(|stored| stored.deleted_at.is_none())
+            .map(|stored| &stored.item)
+    }
+
+    fn record(&mut self, operation: &'static str, item: &MockItem, changed_by: Option<i64>) {
+        self.last_revision_id += 1;
+        let revision = Revision {
+            id: self.last_revision_id,
+            item_id: item.id,
+            operation,
+            title: item.title.clone(),
+            description: item.description.clone(),
+            changed_at: now(),
+            changed_by,
+        };
+        self.revisions.push(revision);
+    }
+
+    fn insert(&mut self, title: String, description: String, owner_id: Option<i64>) -> MockItem {
+        self.last_item_id += 1;
+        let item = MockItem {
+            id: self.last_item_id,
+            title,
+            description,
+            owner_id,
+        };
+        self.record("insert", &item, owner_id);
+        self.items.insert(item.id, StoredItem { item: item.clone(), deleted_at: None });
+        item
+    }
+
+    /// Like the `items_revision_update` trigger, only actual changes are recorded.
+    fn update(
+        &mut self,
+        id: i64,
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Option<MockItem> {
+        let stored = self.items.get_mut(&id).filter(|stored| stored.deleted_at.is_none())?;
+        let before = stored.item.clone();
+        if let Some(title) = title {
+            stored.item.title = title;
+        }
+        if let Some(description) = description {
+            stored.item.description = description;
+        }
+        let item = stored.item.clone();
+        if item != before {
+            self.record("update", &item, changed_by);
+        }
+        Some(item)
+    }
+
+    fn soft_delete(&mut self, id: i64, changed_by: Option<i64>) -> bool {
+        let now = now();
+        let Some(stored) = self.items.get_mut(&id).filter(|stored| stored.deleted_at.is_none()) else {
+            return false;
+        };
+        stored.deleted_at = Some(now);
+        let item = stored.item.clone();
+        self.record("delete", &item, changed_by);
+        true
+    }
+
+    fn username(&self, user_id: Option<i64>) -> Option<String> {
+        user_id
+            .and_then(|id| self.users.get(&id))
+            .map(|(user, _)| user.username.clone())
+    }
+
+    fn revisions_of(&self, item_id: i64) -> impl Iterator<Item = &Revision> {
+        self.revisions.iter().filter(move |revision| revision.item_id == item_id)
+    }
+
+    fn valid_session(&self, token: &str) -> Option<&Session> {
+        self.sessions.get(token).filter(|session| session.expires_at > now())
+    }
+}
+
+fn page<T>(items: Vec<T>, page: u32, page_size: u32) -> (Vec<T>, u32) {
+    let total = items.len() as u32;
+    let offset = (page.saturating_sub(1) * page_size) as usize;
+    let items = items.into_iter().skip(offset).take(page_size as usize).collect();
+    (items, total)
+}
+
+#[async_trait]
+impl ItemRepository for InMemoryRepository {
+    async fn list(
+        &self,
+        filter: &ItemFilter,
+        page_no: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+        Ok(page(self.state().matching(filter), page_no, page_size))
+    }
+
+    async fn matching_ids(&self, filter: &ItemFilter) -> Result<Vec<i64>, RepositoryError> {
+        Ok(self.state().matching(filter).into_iter().map(|item| item.id).collect())
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<MockItem>, RepositoryError> {
+        Ok(self.state().live(id).cloned())
+    }
+
+    async fn get_many(&self, ids: &[i64]) -> Result<Vec<MockItem>, RepositoryError> {
+        let state = self.state();
+        Ok(ids.iter().filter_map(|id| state.live(*id).cloned()).collect())
+    }
+
+    async fn owner_id(&self, id: i64) -> Result<Option<Option<i64>>, RepositoryError> {
+        Ok(self.state().items.get(&id).map(|stored| stored.item.owner_id))
+    }
+
+    async fn insert(
+        &self,
+        title: String,
+        description: String,
+        owner_id: Option<i64>,
+    ) -> Result<MockItem, RepositoryError> {
+        Ok(self.state().insert(title, description, owner_id))
+    }
+
+    async fn insert_all(
+        &self,
+        items: Vec<(String, String)>,
+        owner_id: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError> {
+        let mut state = self.state();
+        Ok(items
+            .into_iter()
+            .map(|(title, description)| state.insert(title, description, owner_id))
+            .collect())
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Option<MockItem>, RepositoryError> {
+        Ok(self.state().update(id, title, description, changed_by))
+    }
+
+    async fn update_all(
+        &self,
+        ids: &[i64],
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError> {
+        let mut state = self.state();
+        Ok(ids
+            .iter()
+            .filter_map(|id| state.update(*id, title.clone(), description.clone(), changed_by))
+            .collect())
+    }
+
+    async fn soft_delete(&self, id: i64, changed_by: Option<i64>) -> Result<bool, RepositoryError> {
+        Ok(self.state().soft_delete(id, changed_by))
+    }
+
+    async fn soft_delete_all(&self, ids: &[i64], changed_by: Option<i64>) -> Result<Vec<i64>, RepositoryError> {
+        let mut state = self.state();
+        Ok(ids.iter().copied().filter(|id| state.soft_delete(*id, changed_by)).collect())
+    }
+
+    async fn list_deleted(
+        &self,
+        owner_id: Option<i64>,
+        page_no: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+        let state = self.state();
+        let mut deleted: Vec<(i64, MockItem)> = state
+            .items
+            .values()
+            .filter(|stored| owner_id.is_none() || stored.item.owner_id == owner_id)
+            .filter_map(|stored| stored.deleted_at.map(|deleted_at| (deleted_at, stored.item.clone())))
+            .collect();
+        deleted.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Ok(page(deleted.into_iter().map(|(_, item)| item).collect(), page_no, page_size))
+    }
+
+    async fn restore(&self, id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+        let mut state = self.state();
+        let Some(stored) = state.items.get_mut(&id).filter(|stored| stored.deleted_at.is_some()) else {
+            return Ok(None);
+        };
+        stored.deleted_at = None;
+        let item = stored.item.clone();
+        state.record("restore", &item, changed_by);
+        Ok(Some(item))
+    }
+
+    async fn purge_deleted(&self, after_days: u32) -> Result<u64, RepositoryError> {
+        let cutoff = now() - i64::from(after_days) * SECONDS_PER_DAY;
+        let mut state = self.state();
+        let purged: Vec<MockItem> = state
+            .items
+            .values()
+            .filter(|stored| matches!(stored.deleted_at, Some(deleted_at) if deleted_at < cutoff))
+            .map(|stored| stored.item.clone())
+            .collect();
+        for item in &purged {
+            state.items.remove(&item.id);
+            state.record("purge", item, None);
+        }
+        Ok(purged.len() as u64)
+    }
+
+    async fn revisions(&self, item_id: i64) -> Result<Vec<ItemRevision>, RepositoryError> {
+        let state = self.state();
+        Ok(state
+            .revisions_of(item_id)
+            .rev()
+            .map(|revision| ItemRevision {
+                id: revision.id,
+                item_id: revision.item_id,
+                operation: revision.operation.to_string(),
+                title: revision.title.clone(),
+                description: revision.description.clone(),
+                changed_at: datetime(revision.changed_at),
+                changed_by: state.username(revision.changed_by),
+            })
+            .collect())
+    }
+
+    async fn revision_item_id(&self, revision_id: i64) -> Result<Option<i64>, RepositoryError> {
+        let state = self.state();
+        Ok(state
+            .revisions
+            .iter()
+            .find(|revision| revision.id == revision_id)
+            .map(|revision| revision.item_id))
+    }
+
+    async fn revert(&self, revision_id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+        let mut state = self.state();
+        let Some(revision) = state.revisions.iter().find(|revision| revision.id == revision_id) else {
+            return Ok(None);
+        };
+        let (item_id, title, description) = (revision.item_id, revision.title.clone(), revision.description.clone());
+        let Some(stored) = state.items.get_mut(&item_id).filter(|stored| stored.deleted_at.is_none()) else {
+            return Ok(None);
+        };
+        let changed = stored.item.title != title || stored.item.description != description;
+        stored.item.title = title;
+        stored.item.description = description;
+        let item = stored.item.clone();
+        if changed {
+            state.record("update", &item, changed_by);
+        }
+        Ok(Some(item))
+    }
+
+    async fn item_stats(&self, days: u32, recent: u32) -> Result<ItemStats, RepositoryError> {
+        let state = self.state();
+        let live: Vec<&MockItem> = state
+            .items
+            .values()
+            .filter(|stored| stored.deleted_at.is_none())
+            .map(|stored| &stored.item)
+            .collect();
+
+        let today = now().div_euclid(SECONDS_PER_DAY);
+        // Items deleted since count as well, they were added on that day all the same.
+        let added_per_day = (today - i64::from(days.max(1)) + 1..=today)
+            .map(|day| DayCount {
+                day: date(day * SECONDS_PER_DAY),
+                count: state
+                    .revisions
+                    .iter()
+                    .filter(|revision| revision.operation == "insert")
+                    .filter(|revision| revision.changed_at.div_euclid(SECONDS_PER_DAY) == day)
+                    .count() as i64,
+            })
+            .collect();
+        let recently_added = live
+            .iter()
+            .rev()
+            .take(recent as usize)
+            .map(|item| ItemActivity {
+                id: item.id,
+                title: item.title.clone(),
+                at: state
+                    .revisions_of(item.id)
+                    .find(|revision| revision.operation == "insert")
+                    .map(|revision| datetime(revision.changed_at)),
+            })
+            .collect();
+        // Reverting an item writes an `update` revision too.
+        let mut edited: Vec<(&Revision, &MockItem)> = live
+            .iter()
+            .filter_map(|item| {
+                state
+                    .revisions_of(item.id)
+                    .filter(|revision| revision.operation == "update")
+                    .last()
+                    .map(|revision| (revision, *item))
+            })
+            .collect();
+        edited.sort_by(|(a, _), (b, _)| b.id.cmp(&a.id));
+        let recently_edited = edited
+            .into_iter()
+            .take(recent as usize)
+            .map(|(revision, item)| ItemActivity {
+                id: item.id,
+                title: item.title.clone(),
+                at: Some(datetime(revision.changed_at)),
+            })
+            .collect();
+
+        Ok(ItemStats {
+            total: live.len() as i64,
+            added_per_day,
+            recently_added,
+            recently_edited,
+        })
+    }
+
+    async fn feed_entries(&self, filter: &ItemFilter, limit: u32) -> Result<Vec<FeedEntry>, RepositoryError> {
+        let state = self.state();
+        Ok(state
+            .matching(filter)
+            .into_iter()
+            .take(limit as usize)
+            .map(|item| {
+                let published = state.revisions_of(item.id).map(|revision| revision.changed_at).min();
+                let updated = state.revisions_of(item.id).map(|revision| revision.changed_at).max();
+                FeedEntry {
+                    id: item.id,
+                    title: item.title,
+                    description: item.description,
+                    published: published.map(rfc3339),
+                    updated: updated.map(rfc3339),
+                    published_unix: published,
+                    updated_unix: updated,
+                }
+            })
+            .collect())
+    }
+
+    async fn last_deletion(&self, filter: &ItemFilter) -> Result<Option<i64>, RepositoryError> {
+        Ok(self
+            .state()
+            .items
+            .values()
+            .filter(|stored| filter.matches(&stored.item))
+            .filter_map(|stored| stored.deleted_at)
+            .max())
+    }
+
+    async fn last_modified(&self, offset: u32, limit: u32) -> Result<Vec<ItemLastModified>, RepositoryError> {
+        let state = self.state();
+        Ok(state
+            .items
+            .values()
+            .filter(|stored| stored.deleted_at.is_none())
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|stored| ItemLastModified {
+                id: stored.item.id,
+                lastmod: state
+                    .revisions_of(stored.item.id)
+                    .map(|revision| revision.changed_at)
+                    .max()
+                    .map(datetime),
+            })
+            .collect())
+    }
+
+    async fn migrate(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<String>, RepositoryError> {
+        Ok(vec![])
+    }
+
+    async fn ping(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryRepository {
+    async fn create_user(&self, username: String, password_hash: String) -> Result<Option<User>, RepositoryError> {
+        let mut state = self.state();
+        if state.users.values().any(|(user, _)| user.username == username) {
+            return Ok(None);
+        }
+        state.last_user_id += 1;
+        let user = User {
+            id: state.last_user_id,
+            username,
+            role: Role::default(),
+        };
+        state.users.insert(user.id, (user.clone(), password_hash));
+        Ok(Some(user))
+    }
+
+    async fn user_by_name(&self, username: &str) -> Result<Option<(User, String)>, RepositoryError> {
+        let state = self.state();
+        Ok(state.users.values().find(|(user, _)| user.username == username).cloned())
+    }
+
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        csrf_token: &str,
+        days: u32,
+    ) -> Result<(), RepositoryError> {
+        let now = now();
+        let mut state = self.state();
+        state.sessions.retain(|_, session| session.expires_at > now);
+        let session = Session {
+            user_id,
+            csrf_token: csrf_token.to_string(),
+            expires_at: now + i64::from(days) * SECONDS_PER_DAY,
+        };
+        state.sessions.insert(token.to_string(), session);
+        Ok(())
+    }
+
+    async fn session_user(&self, token: &str) -> Result<Option<User>, RepositoryError> {
+        let state = self.state();
+        Ok(state
+            .valid_session(token)
+            .and_then(|session| state.users.get(&session.user_id))
+            .map(|(user, _)| user.clone()))
+    }
+
+    async fn session_csrf_token(&self, token: &str) -> Result<Option<String>, RepositoryError> {
+        let state = self.state();
+        Ok(state
+            .valid_session(token)
+            .map(|session| session.csrf_token.clone())
+            .filter(|csrf_token| !csrf_token.is_empty()))
+    }
+
+    async fn delete_session(&self, token: &str) -> Result<(), RepositoryError> {
+        self.state().sessions.remove(token);
+        Ok(())
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::{Postgres, QueryBuilder};
+
+use super::{FeedEntry, ItemLastModified, ItemRepository, RepositoryError, UserRepository};
+use crate::auth::{Role, User};
+use crate::dashboard::{DayCount, ItemActivity, ItemStats};
+use crate::history::ItemRevision;
+use crate::item_filter::{ItemFilter, ItemSort};
+use crate::items::MockItem;
+use crate::monitoring::timed_query;
+
+const MAX_DB_CONNECTIONS: u32 = 5;
+
+/// The migrations in `src/migrations/postgres`.
+static MIGRATOR: Migrator = sqlx::migrate!("src/migrations/postgres");
+
+/// Everything in Postgres. The queries aren't checked at compile time since the `query!` macros
+/// are checked against Items.sqlite. Timestamps are read back like SQLite writes them.
+pub struct PostgresRepository {
+    pool: PgPool,
+}
+
+impl PostgresRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresRepository { pool }
+    }
+
+    pub fn connect_lazy(url: &str) -> Result<Self, String> {
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_DB_CONNECTIONS)
+            .connect_lazy(url)
+            .map_err(|e| e.to_string())?;
+        Ok(PostgresRepository::new(pool))
+    }
+}
+
+/// `expr`, a `TIMESTAMPTZ`, as `YYYY-MM-DD HH:MM:SS` in UTC.
+fn sqlite_datetime(expr: &str) -> String {
+    format!("to_char(({expr}) AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')")
+}
+
+/// Same as `ItemFilter::push_where` for SQLite, but case-insensitive like SQLite's `LIKE`.
+fn push_where(filter: &ItemFilter, qb: &mut QueryBuilder<'_, Postgres>) {
+    qb.push(" WHERE deleted_at IS NULL");
+    push_matches(filter, qb);
+}
+
+/// The condition on `filter.q`, appended to a `WHERE` clause with `AND`.
+fn push_matches(filter: &ItemFilter, qb: &mut QueryBuilder<'_, Postgres>) {
+    if let Some(q) = &filter.q {
+        let pattern = format!(
+            "%{}%",
+            q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        qb.push(" AND (title ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR description ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+}
+
+fn push_order_by(filter: &ItemFilter, qb: &mut QueryBuilder<'_, Postgres>) {
+    qb.push(match filter.sort {
+        ItemSort::IdAsc => " ORDER BY id ASC",
+        ItemSort::IdDesc => " ORDER BY id DESC",
+        ItemSort::TitleAsc => " ORDER BY title ASC, id ASC",
+        ItemSort::TitleDesc => " ORDER BY title DESC, id ASC",
+    });
+}
+
+/// `role` is a `VARCHAR` here, not the type `Role` derives for Postgres.
+fn user((id, username, role): (i64, String, String)) -> User {
+    let role = match role.as_str() {
+        "viewer" => Role::Viewer,
+        "admin" => Role::Admin,
+        _ => Role::Editor,
+    };
+    User { id, username, role }
+}
+
+#[async_trait]
+impl ItemRepository for PostgresRepository {
+    async fn list(
+        &self,
+        filter: &ItemFilter,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+        let offset = page.saturating_sub(1) * page_size;
+        let mut query = QueryBuilder::new("SELECT id, title, description, owner_id FROM items");
+        push_where(filter, &mut query);
+        push_order_by(filter, &mut query);
+        query
+            .push(" LIMIT ")
+            .push_bind(page_size as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
+        let items = timed_query("items.list", query.build_query_as().fetch_all(&self.pool)).await?;
+
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM items");
+        push_where(filter, &mut count);
+        let total_count: i64 =
+            timed_query("items.count", count.build_query_scalar().fetch_one(&self.pool)).await?;
+        Ok((items, total_count as u32))
+    }
+
+    async fn matching_ids(&self, filter: &ItemFilter) -> Result<Vec<i64>, RepositoryError> {
+        let mut query = QueryBuilder::new("SELECT id FROM items");
+        push_where(filter, &mut query);
+        push_order_by(filter, &mut query);
+        Ok(timed_query("items.matching_ids", query.build_query_scalar().fetch_all(&self.pool)).await?)
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as(
+            "SELECT id, title, description, owner_id FROM items WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id);
+        Ok(timed_query("items.get", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn get_many(&self, ids: &[i64]) -> Result<Vec<MockItem>, RepositoryError> {
+        let query = sqlx::query_as::<_, MockItem>(
+            "SELECT id, title, description, owner_id FROM items WHERE deleted_at IS NULL AND id = ANY($1)",
+        )
+        .bind(ids);
+        let items = timed_query("items.get_many", query.fetch_all(&self.pool)).await?;
+        let mut found: HashMap<_, _> = items.into_iter().map(|item| (item.id, item)).collect();
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    async fn owner_id(&self, id: i64) -> Result<Option<Option<i64>>, RepositoryError> {
+        let query = sqlx::query_scalar("SELECT owner_id FROM items WHERE id = $1").bind(id);
+        Ok(timed_query("items.owner_id", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn insert(
+        &self,
+        title: String,
+        description: String,
+        owner_id: Option<i64>,
+    ) -> Result<MockItem, RepositoryError> {
+        let query = sqlx::query_as(
+            "INSERT INTO items (title, description, owner_id, changed_by) VALUES ($1, $2, $3, $3)
+            RETURNING id, title, description, owner_id",
+        )
+        .bind(title)
+        .bind(description)
+        .bind(owner_id);
+        Ok(timed_query("items.insert", query.fetch_one(&self.pool)).await?)
+    }
+
+    async fn insert_all(
+        &self,
+        items: Vec<(String, String)>,
+        owner_id: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError> {
+        // Dropping the transaction on an error rolls it back.
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = Vec::with_capacity(items.len());
+        for (title, description) in items {
+            let query = sqlx::query_as(
+                "INSERT INTO items (title, description, owner_id, changed_by) VALUES ($1, $2, $3, $3)
+                RETURNING id, title, description, owner_id",
+            )
+            .bind(title)
+            .bind(description)
+            .bind(owner_id);
+            inserted.push(timed_query("items.insert", query.fetch_one(&mut tx)).await?);
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as(
+            "UPDATE items SET title = COALESCE($1, title), description = COALESCE($2, description), changed_by = $4
+            WHERE id = $3 AND deleted_at IS NULL
+            RETURNING id, title, description, owner_id",
+        )
+        .bind(title)
+        .bind(description)
+        .bind(id)
+        .bind(changed_by);
+        Ok(timed_query("items.update", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn update_all(
+        &self,
+        ids: &[i64],
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let mut updated = Vec::with_capacity(ids.len());
+        for id in ids {
+            let query = sqlx::query_as(
+                "UPDATE items SET title = COALESCE($1, title), description = COALESCE($2, description), changed_by = $4
+                WHERE id = $3 AND deleted_at IS NULL
+                RETURNING id, title, description, owner_id",
+            )
+            .bind(&title)
+            .bind(&description)
+            .bind(id)
+            .bind(changed_by);
+            updated.extend(timed_query("items.update", query.fetch_optional(&mut tx)).await?);
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn soft_delete(&self, id: i64, changed_by: Option<i64>) -> Result<bool, RepositoryError> {
+        let query = sqlx::query(
+            "UPDATE items SET deleted_at = CURRENT_TIMESTAMP, changed_by = $2 WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(changed_by);
+        let res = timed_query("items.soft_delete", query.execute(&self.pool)).await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn soft_delete_all(&self, ids: &[i64], changed_by: Option<i64>) -> Result<Vec<i64>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = Vec::with_capacity(ids.len());
+        for id in ids {
+            let query = sqlx::query(
+                "UPDATE items SET deleted_at = CURRENT_TIMESTAMP, changed_by = $2 WHERE id = $1 AND deleted_at IS NULL",
+            )
+            .bind(id)
+            .bind(changed_by);
+            if timed_query("items.soft_delete", query.execute(&mut tx)).await?.rows_affected() > 0 {
+                deleted.push(*id);
+            }
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn list_deleted(
+        &self,
+        owner_id: Option<i64>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+        let offset = page.saturating_sub(1) * page_size;
+        let query = sqlx::query_as(
+            "SELECT id, title, description, owner_id FROM items
+            WHERE deleted_at IS NOT NULL AND ($1::BIGINT IS NULL OR owner_id = $1)
+            ORDER BY deleted_at DESC LIMIT $2 OFFSET $3",
+        )
+        .bind(owner_id)
+        .bind(page_size as i64)
+        .bind(offset as i64);
+        let items = timed_query("trash.list", query.fetch_all(&self.pool)).await?;
+
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM items WHERE deleted_at IS NOT NULL AND ($1::BIGINT IS NULL OR owner_id = $1)",
+        )
+        .bind(owner_id);
+        let total_count = timed_query("trash.count", count.fetch_one(&self.pool)).await?;
+        Ok((items, total_count as u32))
+    }
+
+    async fn restore(&self, id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as(
+            "UPDATE items SET deleted_at = NULL, changed_by = $2 WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, title, description, owner_id",
+        )
+        .bind(id)
+        .bind(changed_by);
+        Ok(timed_query("trash.restore", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn purge_deleted(&self, after_days: u32) -> Result<u64, RepositoryError> {
+        let query = sqlx::query(
+            "DELETE FROM items WHERE deleted_at IS NOT NULL
+            AND deleted_at < CURRENT_TIMESTAMP - make_interval(days => $1)",
+        )
+        .bind(after_days as i32);
+        Ok(timed_query("trash.purge", query.execute(&self.pool)).await?.rows_affected())
+    }
+
+    async fn revisions(&self, item_id: i64) -> Result<Vec<ItemRevision>, RepositoryError> {
+        let sql = format!(
+            "SELECT item_revisions.id, item_id, operation, title, description,
+                {} AS changed_at, users.username AS changed_by
+            FROM item_revisions LEFT JOIN users ON users.id = item_revisions.changed_by
+            WHERE item_id = $1 ORDER BY item_revisions.id DESC",
+            sqlite_datetime("changed_at")
+        );
+        let query = sqlx::query_as(&sql).bind(item_id);
+        Ok(timed_query("history.list", query.fetch_all(&self.pool)).await?)
+    }
+
+    async fn revision_item_id(&self, revision_id: i64) -> Result<Option<i64>, RepositoryError> {
+        let query = sqlx::query_scalar("SELECT item_id FROM item_revisions WHERE id = $1").bind(revision_id);
+        Ok(timed_query("history.item_id", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn revert(&self, revision_id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as(
+            "UPDATE items SET title = item_revisions.title, description = item_revisions.description, changed_by = $2
+            FROM item_revisions
+            WHERE item_revisions.id = $1 AND items.id = item_revisions.item_id AND items.deleted_at IS NULL
+            RETURNING items.id, items.title, items.description, items.owner_id",
+        )
+        .bind(revision_id)
+        .bind(changed_by);
+        Ok(timed_query("history.revert", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn item_stats(&self, days: u32, recent: u32) -> Result<ItemStats, RepositoryError> {
+        let total = timed_query(
+            "dashboard.total",
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM items WHERE deleted_at IS NULL").fetch_one(&self.pool),
+        )
+        .await?;
+        // Items deleted since count as well, they were added on that day all the same.
+        let added_per_day = timed_query(
+            "dashboard.added_per_day",
+            sqlx::query_as::<_, DayCount>(
+                "WITH days(day) AS (
+                    SELECT generate_series(
+                        (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date - $1::int,
+                        (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date,
+                        interval '1 day'
+                    )::date
+                )
+                SELECT to_char(days.day, 'YYYY-MM-DD') AS day, COUNT(item_revisions.id) AS count
+                FROM days LEFT JOIN item_revisions
+                    ON (item_revisions.changed_at AT TIME ZONE 'UTC')::date = days.day
+                    AND item_revisions.operation = 'insert'
+                GROUP BY days.day
+                ORDER BY days.day",
+            )
+            .bind((days.max(1) - 1) as i32)
+            .fetch_all(&self.pool),
+        )
+        .await?;
+        let recently_added = timed_query(
+            "dashboard.recently_added",
+            sqlx::query_as::<_, ItemActivity>(&format!(
+                "SELECT items.id, items.title, {} AS at
+                FROM items LEFT JOIN item_revisions
+                    ON item_revisions.item_id = items.id AND item_revisions.operation = 'insert'
+                WHERE items.deleted_at IS NULL
+                ORDER BY items.id DESC
+                LIMIT $1",
+                sqlite_datetime("item_revisions.changed_at")
+            ))
+            .bind(recent as i64)
+            .fetch_all(&self.pool),
+        )
+        .await?;
+        // Reverting an item writes an `update` revision too.
+        let recently_edited = timed_query(
+            "dashboard.recently_edited",
+            sqlx::query_as::<_, ItemActivity>(&format!(
+                "SELECT items.id, items.title, {} AS at
+                FROM items JOIN item_revisions
+                    ON item_revisions.item_id = items.id AND item_revisions.operation = 'update'
+                WHERE items.deleted_at IS NULL
+                GROUP BY items.id
+                ORDER BY MAX(item_revisions.id) DESC
+                LIMIT $1",
+                sqlite_datetime("MAX(item_revisions.changed_at)")
+            ))
+            .bind(recent as i64)
+            .fetch_all(&self.pool),
+        )
+        .await?;
+
+        Ok(ItemStats {
+            total,
+            added_per_day,
+            recently_added,
+            recently_edited,
+        })
+    }
+
+    async fn feed_entries(&self, filter: &ItemFilter, limit: u32) -> Result<Vec<FeedEntry>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            "SELECT id, title, description, published, updated, published_unix, updated_unix
+            FROM items LEFT JOIN (
+                SELECT item_id,
+                    to_char(MIN(changed_at) AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"') AS published,
+                    to_char(MAX(changed_at) AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"') AS updated,
+                    EXTRACT(EPOCH FROM MIN(changed_at))::BIGINT AS published_unix,
+                    EXTRACT(EPOCH FROM MAX(changed_at))::BIGINT AS updated_unix
+                FROM item_revisions GROUP BY item_id
+            ) AS revisions ON revisions.item_id = items.id",
+        );
+        push_where(filter, &mut query);
+        push_order_by(filter, &mut query);
+        query.push(" LIMIT ").push_bind(limit as i64);
+        Ok(timed_query("feed.entries", query.build_query_as().fetch_all(&self.pool)).await?)
+    }
+
+    async fn last_deletion(&self, filter: &ItemFilter) -> Result<Option<i64>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            "SELECT EXTRACT(EPOCH FROM MAX(deleted_at))::BIGINT FROM items WHERE deleted_at IS NOT NULL",
+        );
+        push_matches(filter, &mut query);
+        Ok(timed_query("feed.last_deletion", query.build_query_scalar().fetch_one(&self.pool)).await?)
+    }
+
+    async fn last_modified(&self, offset: u32, limit: u32) -> Result<Vec<ItemLastModified>, RepositoryError> {
+        let sql = format!(
+            "SELECT items.id, {} AS lastmod
+            FROM items LEFT JOIN item_revisions ON item_revisions.item_id = items.id
+            WHERE items.deleted_at IS NULL
+            GROUP BY items.id
+            ORDER BY items.id
+            LIMIT $1 OFFSET $2",
+            sqlite_datetime("MAX(item_revisions.changed_at)")
+        );
+        let query = sqlx::query_as(&sql).bind(limit as i64).bind(offset as i64);
+        Ok(timed_query("sitemap.items", query.fetch_all(&self.pool)).await?)
+    }
+
+    async fn migrate(&self) -> Result<(), RepositoryError> {
+        MIGRATOR.run(&self.pool).await.map_err(|e| RepositoryError(e.to_string()))
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<String>, RepositoryError> {
+        let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| format!("{} {}", migration.version, migration.description))
+            .collect())
+    }
+
+    async fn ping(&self) -> Result<(), RepositoryError> {
+        timed_query("items.ping", sqlx::query("SELECT 1").execute(&self.pool)).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for PostgresRepository {
+    async fn create_user(&self, username: String, password_hash: String) -> Result<Option<User>, RepositoryError> {
+        let query = sqlx::query_as::<_, (i64, String, String)>(
+            "INSERT INTO users (username, password_hash) VALUES ($1, $2) RETURNING id, username, role",
+        )
+        .bind(username)
+        .bind(password_hash);
+        match timed_query("users.insert", query.fetch_one(&self.pool)).await {
+            Ok(row) => Ok(Some(user(row))),
+            // unique_violation
+            Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23505") => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn user_by_name(&self, username: &str) -> Result<Option<(User, String)>, RepositoryError> {
+        let query = sqlx::query_as::<_, (i64, String, String, String)>(
+            "SELECT id, username, role, password_hash FROM users WHERE username = $1",
+        )
+        .bind(username);
+        let row = timed_query("users.by_name", query.fetch_optional(&self.pool)).await?;
+        Ok(row.map(|(id, username, role, password_hash)| (user((id, username, role)), password_hash)))
+    }
+
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        csrf_token: &str,
+        days: u32,
+    ) -> Result<(), RepositoryError> {
+        let purge = sqlx::query("DELETE FROM sessions WHERE expires_at <= CURRENT_TIMESTAMP");
+        timed_query("sessions.purge", purge.execute(&self.pool)).await?;
+        let insert = sqlx::query(
+            "INSERT INTO sessions (id, user_id, csrf_token, expires_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(days => $4))",
+        )
+        .bind(token)
+        .bind(user_id)
+        .bind(csrf_token)
+        .bind(days as i32);
+        timed_query("sessions.insert", insert.execute(&self.pool)).await?;
+        Ok(())
+    }
+
+    async fn session_user(&self, token: &str) -> Result<Option<User>, RepositoryError> {
+        let query = sqlx::query_as::<_, (i64, String, String)>(
+            "SELECT users.id, users.username, users.role FROM sessions
+            JOIN users ON users.id = sessions.user_id
+            WHERE sessions.id = $1 AND sessions.expires_at > CURRENT_TIMESTAMP",
+        )
+        .bind(token);
+        Ok(timed_query("sessions.user", query.fetch_optional(&self.pool)).await?.map(user))
+    }
+
+    async fn session_csrf_token(&self, token: &str) -> Result<Option<String>, RepositoryError> {
+        let query = sqlx::query_scalar::<_, String>(
+            "SELECT csrf_token FROM sessions WHERE id = $1 AND expires_at > CURRENT_TIMESTAMP",
+        )
+        .bind(token);
+        let csrf_token = timed_query("sessions.csrf_token", query.fetch_optional(&self.pool)).await?;
+        Ok(csrf_token.filter(|csrf_token| !csrf_token.is_empty()))
+    }
+
+    async fn delete_session(&self, token: &str) -> Result<(), RepositoryError> {
+        let query = sqlx::query("DELETE FROM sessions WHERE id = $1").bind(token);
+        timed_query("sessions.delete", query.execute(&self.pool)).await?;
+        Ok(())
+    }
+}
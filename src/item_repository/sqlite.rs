@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::{QueryBuilder, SqlitePool};
+
+use super::{FeedEntry, ItemLastModified, ItemRepository, RepositoryError, UserRepository};
+use crate::auth::{Role, User};
+use crate::dashboard::{DayCount, ItemActivity, ItemStats};
+use crate::history::ItemRevision;
+use crate::item_filter::ItemFilter;
+use crate::items::{MockItem, MIGRATOR};
+use crate::monitoring::timed_query;
+
+/// Ids bound per `IN (...)` query, well below SQLite's limit on bound parameters.
+const MAX_BOUND_IDS: usize = 500;
+
+/// Everything in SQLite, see `src/migrations`.
+pub struct SqliteRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteRepository { pool }
+    }
+}
+
+#[async_trait]
+impl ItemRepository for SqliteRepository {
+    async fn list(
+        &self,
+        filter: &ItemFilter,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+        let offset = page.saturating_sub(1) * page_size;
+        let mut query = filter.select_items();
+        query
+            .push(" LIMIT ")
+            .push_bind(page_size)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let items = timed_query("items.list", query.build_query_as().fetch_all(&self.pool)).await?;
+
+        let mut count = filter.count_items();
+        let total_count: i64 =
+            timed_query("items.count", count.build_query_scalar().fetch_one(&self.pool)).await?;
+        Ok((items, total_count as u32))
+    }
+
+    async fn matching_ids(&self, filter: &ItemFilter) -> Result<Vec<i64>, RepositoryError> {
+        let mut query = QueryBuilder::new("SELECT id FROM items");
+        filter.push_where(&mut query);
+        filter.push_order_by(&mut query);
+        Ok(timed_query("items.matching_ids", query.build_query_scalar().fetch_all(&self.pool)).await?)
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as!(
+            MockItem,
+            "SELECT id, title, description, owner_id FROM items WHERE id = $1 AND deleted_at IS NULL",
+            id
+        );
+        Ok(timed_query("items.get", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn get_many(&self, ids: &[i64]) -> Result<Vec<MockItem>, RepositoryError> {
+        let mut found = HashMap::with_capacity(ids.len());
+        for chunk in ids.chunks(MAX_BOUND_IDS) {
+            let mut query = QueryBuilder::new(
+                "SELECT id, title, description, owner_id FROM items WHERE deleted_at IS NULL AND id IN (",
+            );
+            let mut separated = query.separated(", ");
+            for id in chunk {
+                separated.push_bind(*id);
+            }
+            query.push(")");
+            let items: Vec<MockItem> =
+                timed_query("items.get_many", query.build_query_as().fetch_all(&self.pool)).await?;
+            found.extend(items.into_iter().map(|item| (item.id, item)));
+        }
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    async fn owner_id(&self, id: i64) -> Result<Option<Option<i64>>, RepositoryError> {
+        let query = sqlx::query_scalar!("SELECT owner_id FROM items WHERE id = $1", id);
+        Ok(timed_query("items.owner_id", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn insert(
+        &self,
+        title: String,
+        description: String,
+        owner_id: Option<i64>,
+    ) -> Result<MockItem, RepositoryError> {
+        let query = sqlx::query!(
+            "INSERT INTO items (title, description, owner_id, changed_by) VALUES ($1, $2, $3, $3)",
+            title,
+            description,
+            owner_id
+        );
+        let id = timed_query("items.insert", query.execute(&self.pool)).await?.last_insert_rowid();
+        Ok(MockItem {
+            id,
+            title,
+            description,
+            owner_id,
+        })
+    }
+
+    async fn insert_all(
+        &self,
+        items: Vec<(String, String)>,
+        owner_id: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError> {
+        // Dropping the transaction on an error rolls it back.
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = Vec::with_capacity(items.len());
+        for (title, description) in items {
+            let query = sqlx::query!(
+                "INSERT INTO items (title, description, owner_id, changed_by) VALUES ($1, $2, $3, $3)",
+                title,
+                description,
+                owner_id
+            );
+            let id = timed_query("items.insert", query.execute(&mut tx)).await?.last_insert_rowid();
+            inserted.push(MockItem {
+                id,
+                title,
+                description,
+                owner_id,
+            });
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as!(
+            MockItem,
+            "UPDATE items SET title = COALESCE($1, title), description = COALESCE($2, description), changed_by = $4
+            WHERE id = $3 AND deleted_at IS NULL
+            RETURNING id, title, description, owner_id",
+            title,
+            description,
+            id,
+            changed_by
+        );
+        Ok(timed_query("items.update", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn update_all(
+        &self,
+        ids: &[i64],
+        title: Option<String>,
+        description: Option<String>,
+        changed_by: Option<i64>,
+    ) -> Result<Vec<MockItem>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let mut updated = Vec::with_capacity(ids.len());
+        for id in ids {
+            let query = sqlx::query_as!(
+                MockItem,
+                "UPDATE items SET title = COALESCE($1, title), description = COALESCE($2, description), changed_by = $4
+                WHERE id = $3 AND deleted_at IS NULL
+                RETURNING id, title, description, owner_id",
+                title,
+                description,
+                id,
+                changed_by
+            );
+            updated.extend(timed_query("items.update", query.fetch_optional(&mut tx)).await?);
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn soft_delete(&self, id: i64, changed_by: Option<i64>) -> Result<bool, RepositoryError> {
+        let query = sqlx::query!(
+            "UPDATE items SET deleted_at = CURRENT_TIMESTAMP, changed_by = $2 WHERE id = $1 AND deleted_at IS NULL",
+            id,
+            changed_by
+        );
+        let res = timed_query("items.soft_delete", query.execute(&self.pool)).await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn soft_delete_all(&self, ids: &[i64], changed_by: Option<i64>) -> Result<Vec<i64>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = Vec::with_capacity(ids.len());
+        for id in ids {
+            let query = sqlx::query!(
+                "UPDATE items SET deleted_at = CURRENT_TIMESTAMP, changed_by = $2 WHERE id = $1 AND deleted_at IS NULL",
+                id,
+                changed_by
+            );
+            if timed_query("items.soft_delete", query.execute(&mut tx)).await?.rows_affected() > 0 {
+                deleted.push(*id);
+            }
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn list_deleted(
+        &self,
+        owner_id: Option<i64>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+        let offset = page.saturating_sub(1) * page_size;
+        let query = sqlx::query_as!(
+            MockItem,
+            "SELECT id, title, description, owner_id FROM items
+            WHERE deleted_at IS NOT NULL AND ($1 IS NULL OR owner_id = $1)
+            ORDER BY deleted_at DESC LIMIT $2 OFFSET $3",
+            owner_id,
+            page_size,
+            offset
+        );
+        let items = timed_query("trash.list", query.fetch_all(&self.pool)).await?;
+
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM items WHERE deleted_at IS NOT NULL AND ($1 IS NULL OR owner_id = $1)",
+            owner_id
+        );
+        let total_count = timed_query("trash.count", count.fetch_one(&self.pool)).await?;
+        Ok((items, total_count as u32))
+    }
+
+    async fn restore(&self, id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as!(
+            MockItem,
+            "UPDATE items SET deleted_at = NULL, changed_by = $2 WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, title, description, owner_id",
+            id,
+            changed_by
+        );
+        Ok(timed_query("trash.restore", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn purge_deleted(&self, after_days: u32) -> Result<u64, RepositoryError> {
+        let cutoff = format!("-{after_days} days");
+        let query = sqlx::query!(
+            "DELETE FROM items WHERE deleted_at IS NOT NULL AND deleted_at < datetime('now', $1)",
+            cutoff
+        );
+        Ok(timed_query("trash.purge", query.execute(&self.pool)).await?.rows_affected())
+    }
+
+    async fn revisions(&self, item_id: i64) -> Result<Vec<ItemRevision>, RepositoryError> {
+        let query = sqlx::query_as!(
+            ItemRevision,
+            r#"SELECT item_revisions.id, item_id, operation, title, description,
+                changed_at as "changed_at: String", users.username as "changed_by?"
+            FROM item_revisions LEFT JOIN users ON users.id = item_revisions.changed_by
+            WHERE item_id = $1 ORDER BY item_revisions.id DESC"#,
+            item_id
+        );
+        Ok(timed_query("history.list", query.fetch_all(&self.pool)).await?)
+    }
+
+    async fn revision_item_id(&self, revision_id: i64) -> Result<Option<i64>, RepositoryError> {
+        let query = sqlx::query_scalar!("SELECT item_id FROM item_revisions WHERE id = $1", revision_id);
+        Ok(timed_query("history.item_id", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn revert(&self, revision_id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+        let query = sqlx::query_as!(
+            MockItem,
+            "UPDATE items SET (title, description) = (
+                SELECT title, description FROM item_revisions WHERE id = $1
+            ), changed_by = $2
+            WHERE id = (SELECT item_id FROM item_revisions WHERE id = $1) AND deleted_at IS NULL
+            RETURNING id, title, description, owner_id",
+            revision_id,
+            changed_by
+        );
+        Ok(timed_query("history.revert", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn item_stats(&self, days: u32, recent: u32) -> Result<ItemStats, RepositoryError> {
+        let total = timed_query(
+            "dashboard.total",
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM items WHERE deleted_at IS NULL").fetch_one(&self.pool),
+        )
+        .await?;
+        // Items deleted since count as well, they were added on that day all the same.
+        let added_per_day = timed_query(
+            "dashboard.added_per_day",
+            sqlx::query_as::<_, DayCount>(
+                "WITH RECURSIVE days(day) AS (
+                    SELECT date('now', $1)
+                    UNION ALL SELECT date(day, '+1 day') FROM days WHERE day < date('now')
+                )
+                SELECT days.day AS day, COUNT(item_revisions.id) AS count
+                FROM days LEFT JOIN item_revisions
+                    ON date(item_revisions.changed_at) = days.day AND item_revisions.operation = 'insert'
+                GROUP BY days.day
+                ORDER BY days.day",
+            )
+            .bind(format!("-{} days", days.max(1) - 1))
+            .fetch_all(&self.pool),
+        )
+        .await?;
+        let recently_added = timed_query(
+            "dashboard.recently_added",
+            sqlx::query_as::<_, ItemActivity>(
+                "SELECT items.id, items.title, datetime(item_revisions.changed_at) AS at
+                FROM items LEFT JOIN item_revisions
+                    ON item_revisions.item_id = items.id AND item_revisions.operation = 'insert'
+                WHERE items.deleted_at IS NULL
+                ORDER BY items.id DESC
+                LIMIT $1",
+            )
+            .bind(recent)
+            .fetch_all(&self.pool),
+        )
+        .await?;
+        // Reverting an item writes an `update` revision too.
+        let recently_edited = timed_query(
+            "dashboard.recently_edited",
+            sqlx::query_as::<_, ItemActivity>(
+                "SELECT items.id, items.title, datetime(MAX(item_revisions.changed_at)) AS at
+                FROM items JOIN item_revisions
+                    ON item_revisions.item_id = items.id AND item_revisions.operation = 'update'
+                WHERE items.deleted_at IS NULL
+                GROUP BY items.id
+                ORDER BY MAX(item_revisions.id) DESC
+                LIMIT $1",
+            )
+            .bind(recent)
+            .fetch_all(&self.pool),
+        )
+        .await?;
+
+        Ok(ItemStats {
+            total,
+            added_per_day,
+            recently_added,
+            recently_edited,
+        })
+    }
+
+    async fn feed_entries(&self, filter: &ItemFilter, limit: u32) -> Result<Vec<FeedEntry>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            "SELECT id, title, description, published, updated, published_unix, updated_unix
+            FROM items LEFT JOIN (
+                SELECT item_id,
+                    strftime('%Y-%m-%dT%H:%M:%SZ', MIN(changed_at)) AS published,
+                    strftime('%Y-%m-%dT%H:%M:%SZ', MAX(changed_at)) AS updated,
+                    CAST(strftime('%s', MIN(changed_at)) AS INTEGER) AS published_unix,
+                    CAST(strftime('%s', MAX(changed_at)) AS INTEGER) AS updated_unix
+                FROM item_revisions GROUP BY item_id
+            ) AS revisions ON revisions.item_id = items.id",
+        );
+        filter.push_where(&mut query);
+        filter.push_order_by(&mut query);
+        query.push(" LIMIT ").push_bind(limit);
+        Ok(timed_query("feed.entries", query.build_query_as().fetch_all(&self.pool)).await?)
+    }
+
+    async fn last_deletion(&self, filter: &ItemFilter) -> Result<Option<i64>, RepositoryError> {
+        let mut query = QueryBuilder::new(
+            "SELECT CAST(strftime('%s', MAX(deleted_at)) AS INTEGER) FROM items WHERE deleted_at IS NOT NULL",
+        );
+        filter.push_matches(&mut query);
+        Ok(timed_query("feed.last_deletion", query.build_query_scalar().fetch_one(&self.pool)).await?)
+    }
+
+    async fn last_modified(&self, offset: u32, limit: u32) -> Result<Vec<ItemLastModified>, RepositoryError> {
+        let query = sqlx::query_as::<_, ItemLastModified>(
+            "SELECT items.id, MAX(item_revisions.changed_at) AS lastmod
+            FROM items LEFT JOIN item_revisions ON item_revisions.item_id = items.id
+            WHERE items.deleted_at IS NULL
+            GROUP BY items.id
+            ORDER BY items.id
+            LIMIT $1 OFFSET $2",
+        )
+        .bind(limit)
+        .bind(offset);
+        Ok(timed_query("sitemap.items", query.fetch_all(&self.pool)).await?)
+    }
+
+    async fn migrate(&self) -> Result<(), RepositoryError> {
+        MIGRATOR.run(&self.pool).await.map_err(|e| RepositoryError(e.to_string()))
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<String>, RepositoryError> {
+        let applied = sqlx::query_scalar!("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| format!("{} {}", migration.version, migration.description))
+            .collect())
+    }
+
+    async fn ping(&self) -> Result<(), RepositoryError> {
+        timed_query("items.ping", sqlx::query("SELECT 1").execute(&self.pool)).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqliteRepository {
+    async fn create_user(&self, username: String, password_hash: String) -> Result<Option<User>, RepositoryError> {
+        let query = sqlx::query_as!(
+            User,
+            r#"INSERT INTO users (username, password_hash) VALUES ($1, $2)
+            RETURNING id, username, role as "role: Role""#,
+            username,
+            password_hash
+        );
+        match timed_query("users.insert", query.fetch_one(&self.pool)).await {
+            Ok(user) => Ok(Some(user)),
+            Err(sqlx::Error::Database(e)) if e.message().contains("UNIQUE") => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn user_by_name(&self, username: &str) -> Result<Option<(User, String)>, RepositoryError> {
+        let query = sqlx::query!(
+            r#"SELECT id, username, password_hash, role as "role: Role" FROM users WHERE username = $1"#,
+            username
+        );
+        let row = timed_query("users.by_name", query.fetch_optional(&self.pool)).await?;
+        Ok(row.map(|row| {
+            let user = User {
+                id: row.id,
+                username: row.username,
+                role: row.role,
+            };
+            (user, row.password_hash)
+        }))
+    }
+
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        csrf_token: &str,
+        days: u32,
+    ) -> Result<(), RepositoryError> {
+        let expires = format!("+{days} days");
+        let purge = sqlx::query!("DELETE FROM sessions WHERE expires_at <= CURRENT_TIMESTAMP");
+        timed_query("sessions.purge", purge.execute(&self.pool)).await?;
+        let insert = sqlx::query!(
+            "INSERT INTO sessions (id, user_id, csrf_token, expires_at) VALUES ($1, $2, $3, datetime('now', $4))",
+            token,
+            user_id,
+            csrf_token,
+            expires
+        );
+        timed_query("sessions.insert", insert.execute(&self.pool)).await?;
+        Ok(())
+    }
+
+    async fn session_user(&self, token: &str) -> Result<Option<User>, RepositoryError> {
+        let query = sqlx::query_as!(
+            User,
+            r#"SELECT users.id, users.username, users.role as "role: Role" FROM sessions
+            JOIN users ON users.id = sessions.user_id
+            WHERE sessions.id = $1 AND sessions.expires_at > CURRENT_TIMESTAMP"#,
+            token
+        );
+        Ok(timed_query("sessions.user", query.fetch_optional(&self.pool)).await?)
+    }
+
+    async fn session_csrf_token(&self, token: &str) -> Result<Option<String>, RepositoryError> {
+        let query = sqlx::query_scalar!(
+            "SELECT csrf_token FROM sessions WHERE id = $1 AND expires_at > CURRENT_TIMESTAMP",
+            token
+        );
+        let csrf_token = timed_query("sessions.csrf_token", query.fetch_optional(&self.pool)).await?;
+        Ok(csrf_token.filter(|csrf_token| !csrf_token.is_empty()))
+    }
+
+    async fn delete_session(&self, token: &str) -> Result<(), RepositoryError> {
+        let query = sqlx::query!("DELETE FROM sessions WHERE id = $1", token);
+        timed_query("sessions.delete", query.execute(&self.pool)).await?;
+        Ok(())
+    }
+}
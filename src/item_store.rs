@@ -0,0 +1,118 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        // Data access for `items` shared by the server functions and the REST API, backed by the
+        // configured `ItemRepository`. Mutations publish their `ItemEvent` here so open clients see
+        // them no matter which endpoint made them.
+        use crate::item_events::{self, ItemEvent};
+        use crate::item_filter::ItemFilter;
+        use crate::item_repository::{repository, RepositoryError};
+        use crate::items::MockItem;
+
+        /// One page of the items matching `filter` and the total number of matching items.
+        pub async fn list(
+            filter: &ItemFilter,
+            page: u32,
+            page_size: u32,
+        ) -> Result<(Vec<MockItem>, u32), RepositoryError> {
+            repository().list(filter, page, page_size).await
+        }
+
+        pub async fn get(id: i64) -> Result<Option<MockItem>, RepositoryError> {
+            repository().get(id).await
+        }
+
+        /// The owner of an item, deleted or not. `None` if there is no such item.
+        pub async fn owner_id(id: i64) -> Result<Option<Option<i64>>, RepositoryError> {
+            repository().owner_id(id).await
+        }
+
+        pub async fn insert(
+            title: String,
+            description: String,
+            owner_id: Option<i64>,
+        ) -> Result<MockItem, RepositoryError> {
+            let item = repository().insert(title, description, owner_id).await?;
+            item_events::publish(ItemEvent::Added(item.clone()));
+            Ok(item)
+        }
+
+        /// Adds `(title, description)` items owned by `owner_id`, all of them or none.
+        pub async fn insert_all(
+            items: Vec<(String, String)>,
+            owner_id: Option<i64>,
+        ) -> Result<Vec<MockItem>, RepositoryError> {
+            let items = repository().insert_all(items, owner_id).await?;
+            for item in &items {
+                item_events::publish(ItemEvent::Added(item.clone()));
+            }
+            Ok(items)
+        }
+
+        /// Updates the given fields of a not deleted item, `None` leaves a field as it is.
+        /// `changed_by` is the user making the change, for the item's history.
+        pub async fn update(
+            id: i64,
+            title: Option<String>,
+            description: Option<String>,
+            changed_by: Option<i64>,
+        ) -> Result<Option<MockItem>, RepositoryError> {
+            let item = repository().update(id, title, description, changed_by).await?;
+            if let Some(item) = &item {
+                item_events::publish(ItemEvent::Updated(item.clone()));
+            }
+            Ok(item)
+        }
+
+        /// Moves an item to the trash, returns `false` if there was no such (not deleted) item.
+        pub async fn soft_delete(id: i64, changed_by: Option<i64>) -> Result<bool, RepositoryError> {
+            let deleted = repository().soft_delete(id, changed_by).await?;
+            if deleted {
+                item_events::publish(ItemEvent::Removed(id));
+            }
+            Ok(deleted)
+        }
+
+        /// [update] of each of `ids` in one transaction, returns the updated items.
+        pub async fn update_all(
+            ids: &[i64],
+            title: Option<String>,
+            description: Option<String>,
+            changed_by: Option<i64>,
+        ) -> Result<Vec<MockItem>, RepositoryError> {
+            let items = repository().update_all(ids, title, description, changed_by).await?;
+            for item in &items {
+                item_events::publish(ItemEvent::Updated(item.clone()));
+            }
+            Ok(items)
+        }
+
+        /// [soft_delete] of each of `ids` in one transaction, returns the ids of the deleted items.
+        pub async fn soft_delete_all(ids: &[i64], changed_by: Option<i64>) -> Result<Vec<i64>, RepositoryError> {
+            let deleted = repository().soft_delete_all(ids, changed_by).await?;
+            for id in &deleted {
+                item_events::publish(ItemEvent::Removed(*id));
+            }
+            Ok(deleted)
+        }
+
+        /// Takes an item out of the trash, `None` if there was no such deleted item.
+        pub async fn restore(id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+            let item = repository().restore(id, changed_by).await?;
+            if let Some(item) = &item {
+                item_events::publish(ItemEvent::Added(item.clone()));
+            }
+            Ok(item)
+        }
+
+        /// Sets an item back to one of its revisions, `None` if there is no such revision.
+        pub async fn revert(revision_id: i64, changed_by: Option<i64>) -> Result<Option<MockItem>, RepositoryError> {
+            let item = repository().revert(revision_id, changed_by).await?;
+            if let Some(item) = &item {
+                item_events::publish(ItemEvent::Updated(item.clone()));
+            }
+            Ok(item)
+        }
+    }
+}
@@ -1,104 +1,164 @@
 use cfg_if::cfg_if;
 use leptos::*;
+use leptos_meta::{Link, LinkProps};
 use leptos_router::*;
 use serde::{Deserialize, Serialize};
 
+use crate::auth::AuthContext;
+use crate::authz::Permission;
+use crate::bulk::{
+    BulkActionBar, BulkActionBarProps, BulkDeleteItems, BulkExportItems, BulkSelectCheckbox,
+    BulkSelectCheckboxProps, BulkSelection, BulkUpdateItems,
+};
+use crate::csrf::{
+    create_csrf_action, create_csrf_multi_action, CsrfActionForm, CsrfActionFormProps, CsrfMultiActionForm,
+    CsrfMultiActionFormProps,
+};
+use crate::export::{ExportFormat, EXPORT_ITEMS_PATH};
+use crate::feed::{ATOM_FEED_PATH, RSS_FEED_PATH};
+use crate::history::{ItemHistory, ItemHistoryProps, RevertItem};
+use crate::import::{ImportItems, ImportItemsProps};
+#[cfg(not(feature = "ssr"))]
+use crate::item_events::{self, ItemEvent};
+use crate::item_filter::{ItemFilter, ItemSort, SEARCH_QUERY_PARAM, SORT_QUERY_PARAM};
+use crate::page_meta::{preloaded_item, PageMeta, PageMetaProps};
 use crate::pagination::pagination_components::{
     Pagination, PaginationProps, PaginationStateContext,
 };
+#[cfg(not(feature = "ssr"))]
+use crate::pagination::pagination_state::PaginationState;
+use crate::rate_limit::describe_error;
+use crate::trash::{RestoreItem, UndoRemove, UndoRemoveProps};
 
 cfg_if! {
     if #[cfg(feature = "ssr")] {
-        use sqlx::{Connection, SqliteConnection};
+        use std::sync::OnceLock;
+
+        use sqlx::migrate::Migrator;
+        use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+
+        use crate::authz::{authorize, authorize_item, AuthError};
+        use crate::item_store;
 
-        pub async fn db() -> Result<SqliteConnection, ServerFnError> {
-            let conn = SqliteConnection::connect("sqlite:Items.sqlite")
-                    .await
-                    .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
-            Ok(conn)
+        const MAX_DB_CONNECTIONS: u32 = 5;
+
+        static POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+        /// The migrations in `src/migrations`, applied to Items.sqlite at startup.
+        pub static MIGRATOR: Migrator = sqlx::migrate!("src/migrations");
+
+        /// Uses `pool` instead of Items.sqlite, fails if the pool is already in use.
+        pub fn set_pool(pool: SqlitePool) -> Result<(), SqlitePool> {
+            POOL.set(pool)
+        }
+
+        /// The pool if anything has used it yet, which is never with another backend than SQLite.
+        pub fn existing_pool() -> Option<&'static SqlitePool> {
+            POOL.get()
+        }
+
+        /// The connection pool shared by the whole server, connecting lazily on first use.
+        pub fn pool() -> &'static SqlitePool {
+            POOL.get_or_init(|| {
+                SqlitePoolOptions::new()
+                    .max_connections(MAX_DB_CONNECTIONS)
+                    .connect_lazy("sqlite:Items.sqlite")
+                    .expect("invalid database URL")
+            })
         }
 
         pub fn register_server_functions() {
             _ = GetItems::register();
             _ = GetItem::register();
             _ = AddItem::register();
+            _ = UpdateItem::register();
             _ = RemoveItem::register();
         }
     }
 }
 
+pub const MAX_TITLE_LEN: usize = 100;
+pub const MAX_DESCRIPTION_LEN: usize = 1000;
+
+/// The rules every new or edited item has to satisfy, returns all violations at once.
+pub fn validate_item(title: &str, description: &str) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if title.trim().is_empty() {
+        errors.push("title must not be empty".to_string());
+    }
+    if title.chars().count() > MAX_TITLE_LEN {
+        errors.push(format!("title must be at most {MAX_TITLE_LEN} characters"));
+    }
+    if description.chars().count() > MAX_DESCRIPTION_LEN {
+        errors.push(format!("description must be at most {MAX_DESCRIPTION_LEN} characters"));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 #[server(GetItems, "/api")]
 pub async fn get_items(
     cx: Scope,
     page: u32,
     page_size: u32,
+    q: Option<String>,
+    sort: ItemSort,
 ) -> Result<(Vec<MockItem>, u32), ServerFnError> {
+    authorize(cx, Permission::ReadItems).await?;
     let req_parts = use_context::<leptos_axum::RequestParts>(cx);
 
     if let Some(req_parts) = req_parts {
-        log::info!("Uri = {:?}", req_parts.uri);
+        tracing::debug!(uri = %req_parts.uri, page, page_size, "listing items");
     }
 
-    let mut conn = db().await?;
-
-    let offset = page.saturating_sub(1) * page_size.clone();
-    let limit = page_size;
-    let items: Vec<MockItem> = sqlx::query_as!(
-        MockItem,
-        "SELECT * FROM items LIMIT $1 OFFSET $2",
-        limit,
-        offset
-    )
-    .fetch_all(&mut conn)
-    .await
-    .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
-
-    let mut conn = db().await?;
-    let total_count = sqlx::query!("SELECT COUNT(*) as count FROM items")
-        .fetch_one(&mut conn)
+    item_store::list(&ItemFilter { q, sort }, page, page_size)
         .await
-        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
-
-    Ok((items, total_count.count as u32))
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
 }
 
 #[server(GetItem, "/api")]
-pub async fn get_item(id: i32) -> Result<MockItem, ServerFnError> {
-    let mut conn = db().await?;
+pub async fn get_item(cx: Scope, id: i32) -> Result<MockItem, ServerFnError> {
+    authorize(cx, Permission::ReadItems).await?;
     std::thread::sleep(std::time::Duration::from_secs(1));
 
-    let item = sqlx::query_as!(MockItem, "SELECT * FROM items WHERE id = $1", id)
-        .fetch_one(&mut conn)
+    item_store::get(id as i64)
         .await
-        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
-    Ok(item)
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| AuthError::NotFound.into())
 }
 
 #[server(AddItem, "/api")]
-pub async fn add_item(title: String, description: String) -> Result<(), ServerFnError> {
-    let mut conn = db().await?;
-
+pub async fn add_item(cx: Scope, title: String, description: String) -> Result<(), ServerFnError> {
+    let user = authorize(cx, Permission::CreateItem).await?;
+    validate_item(&title, &description).map_err(|errors| ServerFnError::Args(errors.join(", ")))?;
     std::thread::sleep(std::time::Duration::from_secs(1));
 
-    sqlx::query!(
-        "INSERT INTO items (title, description) VALUES ($1, $2)",
-        title,
-        description
-    )
-    .execute(&mut conn)
-    .await
-    .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    item_store::insert(title, description, user.map(|user| user.id))
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    Ok(())
+}
+
+#[server(UpdateItem, "/api")]
+pub async fn update_item(cx: Scope, id: i64, title: String, description: String) -> Result<(), ServerFnError> {
+    validate_item(&title, &description).map_err(|errors| ServerFnError::Args(errors.join(", ")))?;
+    let user = authorize_item(cx, id).await?;
 
+    item_store::update(id, Some(title), Some(description), user.map(|user| user.id))
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
     Ok(())
 }
 
 #[server(RemoveItem, "/api")]
-pub async fn remove_item(id: i32) -> Result<(), ServerFnError> {
-    let mut conn = db().await?;
+pub async fn remove_item(cx: Scope, id: i32) -> Result<(), ServerFnError> {
+    let user = authorize_item(cx, id as i64).await?;
     std::thread::sleep(std::time::Duration::from_secs(1));
 
-    sqlx::query!("DELETE FROM items WHERE id = $1", id)
-        .execute(&mut conn)
+    item_store::soft_delete(id as i64, user.map(|user| user.id))
         .await
         .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
     Ok(())
@@ -106,11 +166,31 @@ pub async fn remove_item(id: i32) -> Result<(), ServerFnError> {
 
 #[component]
 pub fn ItemsView(cx: Scope) -> impl IntoView {
+    let query = use_query_map(cx);
+    let filter = move || query.with(ItemFilter::from_query_map);
+    let auth = use_context::<AuthContext>(cx).unwrap();
+    let can_create = move || Permission::CreateItem.allows(auth.current_user().as_ref());
+
     view! {cx,
         <div>
             <h1>"Paginated Items"</h1>
+            <A href="/items/trash">"Trash"</A>
+            <ItemFilterForm/>
+            {move || can_create().then(|| view! { cx, <ImportItems/> })}
+            {[ExportFormat::Csv, ExportFormat::Jsonl, ExportFormat::Json]
+                .into_iter()
+                .map(|format| view! { cx,
+                    <a href=move || format!("{EXPORT_ITEMS_PATH}?format={}&{}", format.extension(), filter().to_query_string())
+                        download="">
+                        {format!("Export {} ", format.extension().to_uppercase())}
+                    </a>
+                })
+                .collect::<Vec<_>>()}
+            <Link rel="alternate" type_="application/atom+xml" href=ATOM_FEED_PATH.to_string()/>
+            <a href=move || format!("{ATOM_FEED_PATH}?{}", filter().to_query_string())>"Atom feed "</a>
+            <a href=move || format!("{RSS_FEED_PATH}?{}", filter().to_query_string())>"RSS feed"</a>
                 <Pagination
-                    pagination_link=Box::new(|page, page_size| format!("/items?page={}&page_size={}", page, page_size))
+                    pagination_link=Box::new(move |page, page_size| format!("/items?{}", filter().to_page_query_string(page, page_size)))
                     page_query_param="page".to_string()
                     page_size_query_param="page_size".to_string()>
                     <Items/>
@@ -120,11 +200,37 @@ pub fn ItemsView(cx: Scope) -> impl IntoView {
     }
 }
 
+#[component]
+pub fn ItemFilterForm(cx: Scope) -> impl IntoView {
+    let query = use_query_map(cx);
+    let filter = move || query.with(ItemFilter::from_query_map);
+    let page_size = move || query.with(|q| q.get("page_size").cloned().unwrap_or_default());
+
+    view! { cx,
+        <Form method="GET" action="/items">
+            <input type="search" name=SEARCH_QUERY_PARAM placeholder="Search"
+                prop:value=move || filter().q.unwrap_or_default()/>
+            <select name=SORT_QUERY_PARAM prop:value=move || filter().sort.as_str()>
+                {ItemSort::ALL
+                    .into_iter()
+                    .map(|sort| view! { cx,
+                        <option value=sort.as_str() selected=move || filter().sort == sort>{sort.label()}</option>
+                    })
+                    .collect::<Vec<_>>()}
+            </select>
+            <input type="hidden" name="page_size" prop:value=page_size/>
+            <input type="submit" value="Search"/>
+        </Form>
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow, utoipa::ToSchema))]
 pub struct MockItem {
     pub id: i64,
     pub title: String,
     pub description: String,
+    pub owner_id: Option<i64>,
 }
 
 #[derive(Clone)]
@@ -136,8 +242,8 @@ struct ItemsActions {
 impl ItemsActions {
     fn new(cx: Scope) -> Self {
         ItemsActions {
-            add_item: create_server_multi_action::<AddItem>(cx),
-            remove_item: create_server_action::<RemoveItem>(cx),
+            add_item: create_csrf_multi_action::<AddItem>(cx),
+            remove_item: create_csrf_action::<RemoveItem>(cx),
         }
     }
 }
@@ -149,22 +255,40 @@ pub fn Items(cx: Scope) -> impl IntoView {
         set_pagination_state,
     } = use_context(cx).unwrap();
 
-    let add_item = create_server_multi_action::<AddItem>(cx);
-    let remove_item = create_server_action::<RemoveItem>(cx);
+    let add_item = create_csrf_multi_action::<AddItem>(cx);
+    let remove_item = create_csrf_action::<RemoveItem>(cx);
+    let restore_item = create_csrf_action::<RestoreItem>(cx);
+    let bulk_delete = create_csrf_action::<BulkDeleteItems>(cx);
+    let bulk_update = create_csrf_action::<BulkUpdateItems>(cx);
+    let bulk_export = create_csrf_action::<BulkExportItems>(cx);
+    let selection = create_rw_signal(cx, BulkSelection::default());
+
+    let query = use_query_map(cx);
+    let filter = move || query.with(ItemFilter::from_query_map);
+    let auth = use_context::<AuthContext>(cx).unwrap();
+    let can_create = move || Permission::CreateItem.allows(auth.current_user().as_ref());
 
     log::info!("init Items");
 
     let paginated_items = create_resource(
         cx,
         move || {
+            let ps = pagination_state();
             (
-                pagination_state(),
-                add_item.version().get(),
-                remove_item.version().get(),
+                (ps.page(), ps.page_size()),
+                filter(),
+                (
+                    add_item.version().get(),
+                    remove_item.version().get(),
+                    restore_item.version().get(),
+                    bulk_delete.version().get(),
+                    bulk_update.version().get(),
+                ),
             )
         },
-        move |(ps, _, _)| async move {
-            let res = get_items(cx, ps.page() as u32, ps.page_size() as u32).await;
+        move |((page, page_size), filter, _)| async move {
+            let ItemFilter { q, sort } = filter;
+            let res = get_items(cx, page as u32, page_size as u32, q, sort).await;
             match res {
                 Ok((items, total_count)) => {
                     set_pagination_state.update(|ps| ps.set_element_count(total_count as usize));
@@ -177,15 +301,98 @@ pub fn Items(cx: Scope) -> impl IntoView {
             }
         },
     );
+
+    // The loaded page as changed by item events since, `None` until an event patched it.
+    let patched_items = create_rw_signal(cx, None::<Vec<MockItem>>);
+    create_effect(cx, move |_| {
+        paginated_items.with(|_| ());
+        patched_items.set(None);
+    });
+    let items = move || {
+        paginated_items
+            .read()
+            .map(|loaded| patched_items.get().unwrap_or(loaded))
+    };
+
+    #[cfg(not(feature = "ssr"))]
+    item_events::subscribe_item_events(cx, move |event| {
+        let Some(page_items) = items() else {
+            return;
+        };
+        let ps = pagination_state.get_untracked();
+        match patch_page(&event, page_items, &filter(), &ps) {
+            Some((page_items, element_count)) => {
+                patched_items.set(Some(page_items));
+                set_pagination_state.update(|ps| ps.set_element_count(element_count));
+            }
+            None => paginated_items.refetch(),
+        }
+    });
+
+    create_effect(cx, move |_| {
+        if bulk_delete.version().get() > 0 {
+            selection.set(BulkSelection::default());
+        }
+    });
+    let page_ids = Signal::derive(cx, move || {
+        items()
+            .map(|items| items.iter().map(|item| item.id).collect())
+            .unwrap_or_default()
+    });
+
+    let pending_adds = move || {
+        add_item
+            .submissions()
+            .get()
+            .into_iter()
+            .filter_map(|submission| submission.input.get())
+            .map(|input| {
+                view! { cx, <div class="pending">
+                    <h3>{format!("{} [...]", input.title)}</h3>
+                    <p>{input.description}</p>
+                </div>}
+            })
+            .collect::<Vec<_>>()
+    };
+    // Only the outcome of the latest finished add, a successful retry clears the error.
+    let failed_add = move || {
+        add_item
+            .submissions()
+            .get()
+            .into_iter()
+            .rev()
+            .find_map(|submission| submission.value.get())
+            .and_then(Result::err)
+            .map(|e| view! { cx, <p class="error">"Failed to add item: " {describe_error(&e)}</p> })
+    };
+    let failed_remove = move || match remove_item.value().get() {
+        Some(Err(e)) => Some(view! { cx, <p class="error">"Failed to remove item: " {describe_error(&e)}</p> }),
+        _ => None,
+    };
+
     view! { cx, <div>
-        <MultiActionForm action=add_item>
-            <h3>"Add Item"</h3>
-            <label>"Title" <input type="text" name="title"/></label>
-            <label>"Description" <input type="text" name="description"/></label>
-            <input type="submit" value="Add"/>
-        </MultiActionForm>
+        {move || can_create().then(|| view! { cx,
+            <CsrfMultiActionForm action=add_item>
+                <h3>"Add Item"</h3>
+                <label>"Title" <input type="text" name="title"/></label>
+                <label>"Description" <input type="text" name="description"/></label>
+                <input type="submit" value="Add"/>
+            </CsrfMultiActionForm>
+        })}
+        {failed_add}
+        {failed_remove}
+        <UndoRemove remove_item=remove_item restore_item=restore_item/>
+        <BulkActionBar
+            selection=selection
+            filter=Signal::derive(cx, filter)
+            page_ids=page_ids
+            can_edit=Signal::derive(cx, can_create)
+            bulk_delete=bulk_delete
+            bulk_update=bulk_update
+            bulk_export=bulk_export/>
+        {pending_adds}
         <Transition fallback=move || view! {cx, <div>"Loading..."</div>}>
-            {move || match paginated_items.read() {
+            {move || match items() {
                 None => None,
                 Some(items) => {
                     let items = items.clone();
@@ -195,7 +402,7 @@ pub fn Items(cx: Scope) -> impl IntoView {
                                 each=move || items.clone()
                                 key=|item| item.id.clone()
                                 view=move |item| {
-                                    view!{ cx, <MockItem item=item remove_item=remove_item/> }
+                                    view!{ cx, <MockItem item=item remove_item=remove_item selection=selection/> }
                                 }/>
                         </div>}.into_any()
                     )
@@ -205,6 +412,56 @@ pub fn Items(cx: Scope) -> impl IntoView {
     </div>}
 }
 
+/// Applies an item event to the loaded page `items` of the list at `ps` and its total, as long as
+/// that can be done without the server. `None` when the page has to be refetched, e.g. because
+/// items slid onto or off it or the sort by title leaves the item's place unknown.
+#[cfg(not(feature = "ssr"))]
+fn patch_page(
+    event: &ItemEvent,
+    mut items: Vec<MockItem>,
+    filter: &ItemFilter,
+    ps: &PaginationState,
+) -> Option<(Vec<MockItem>, usize)> {
+    let total = ps.element_count();
+    let skip = ps.calc_skip();
+    let sorted_by_id = matches!(filter.sort, ItemSort::IdAsc | ItemSort::IdDesc);
+    match event {
+        ItemEvent::Added(item) if !filter.matches(item) => Some((items, total)),
+        // the newest item is the last of the list
+        ItemEvent::Added(item) if filter.sort == ItemSort::IdAsc => {
+            if (skip..skip + ps.page_size()).contains(&total) {
+                items.push(item.clone());
+            }
+            Some((items, total + 1))
+        }
+        // the newest item is the first of the list, every page after the first shifts
+        ItemEvent::Added(item) if filter.sort == ItemSort::IdDesc && ps.page() <= 1 => {
+            items.insert(0, item.clone());
+            items.truncate(ps.page_size());
+            Some((items, total + 1))
+        }
+        ItemEvent::Added(_) => None,
+        ItemEvent::Removed(id) => {
+            let position = items.iter().position(|item| item.id == *id)?;
+            // unless the page is the last one, the first item of the next page moves up
+            if skip + items.len() < total {
+                return None;
+            }
+            items.remove(position);
+            Some((items, total.saturating_sub(1)))
+        }
+        ItemEvent::Updated(item) => match items.iter().position(|loaded| loaded.id == item.id) {
+            Some(position) if sorted_by_id && filter.matches(item) => {
+                items[position] = item.clone();
+                Some((items, total))
+            }
+            // without a search or a sort by title an edit can't move an item onto the page
+            None if sorted_by_id && filter.q.is_none() => Some((items, total)),
+            _ => None,
+        },
+    }
+}
+
 #[component]
 pub fn ItemView(cx: Scope) -> impl IntoView {
     let params = use_params_map(cx);
@@ -212,20 +469,62 @@ pub fn ItemView(cx: Scope) -> impl IntoView {
     let id_string = move || params().get("id").cloned();
     let id = move || id_string().and_then(|s| s.parse().ok());
 
-    let remove_item = create_server_action::<RemoveItem>(cx);
+    let remove_item = create_csrf_action::<RemoveItem>(cx);
+    let update_item = create_csrf_action::<UpdateItem>(cx);
+    let revert_item = create_csrf_action::<RevertItem>(cx);
+    let auth = use_context::<AuthContext>(cx).unwrap();
 
-    let item_res: Resource<i32, Result<MockItem, ServerFnError>> = create_resource(
+    let item_res = create_resource(
         cx,
-        move || id().unwrap_or_default(),
-        move |id| async move { get_item(id).await },
+        move || {
+            (
+                id().unwrap_or_default(),
+                update_item.version().get(),
+                revert_item.version().get(),
+            )
+        },
+        move |(id, _, _)| async move { get_item(cx, id).await },
     );
+    // The loaded item once there is one, during SSR the one the server loaded up front.
+    let preloaded = preloaded_item(cx);
+    let meta_item = move || {
+        item_res
+            .read()
+            .and_then(Result::ok)
+            .or_else(|| preloaded.clone())
+    };
+    let meta_title = {
+        let meta_item = meta_item.clone();
+        move || meta_item().map_or_else(|| "Item".to_string(), |item| item.title)
+    };
+    let meta_description = move || meta_item().map_or_else(String::new, |item| item.description);
+    let failed_update = move || match update_item.value().get() {
+        Some(Err(e)) => Some(view! { cx, <p class="error">"Failed to save item: " {describe_error(&e)}</p> }),
+        _ => None,
+    };
 
     view! {cx,
+        <PageMeta title=meta_title description=meta_description og_type="article"/>
         <A href="/items">"Back to Items"</A>
         <Transition fallback=move || view! {cx, <p>"Loading..."</p> }>
             {move ||
                 item_res.read().map(|item| match item {
-                    Ok(item) => { (view! {cx, <MockItem item=item.clone() remove_item=remove_item/> }).into_view(cx) },
+                    Ok(item) => { (view! {cx,
+                        <MockItem item=item.clone() remove_item=remove_item/>
+                        {Permission::ModifyItem { owner_id: item.owner_id }
+                            .allows(auth.current_user().as_ref())
+                            .then(|| view! { cx,
+                                <CsrfActionForm action=update_item>
+                                    <h3>"Edit Item"</h3>
+                                    <input type="hidden" name="id" value={item.id}/>
+                                    <label>"Title" <input type="text" name="title" value={item.title.clone()}/></label>
+                                    <label>"Description" <input type="text" name="description" value={item.description.clone()}/></label>
+                                    <input type="submit" value="Save"/>
+                                </CsrfActionForm>
+                                {failed_update}
+                            })}
+                        <ItemHistory item_id=item.id owner_id=item.owner_id update_item=update_item revert_item=revert_item/>
+                    }).into_view(cx) },
                     Err(e) => view! { cx, <pre class="error">"Server Error: " {e.to_string()}</pre>}.into_view(cx) ,
                 })
             }
@@ -238,16 +537,26 @@ pub fn MockItem(
     cx: Scope,
     item: MockItem,
     remove_item: Action<RemoveItem, Result<(), ServerFnError>>,
+    #[prop(optional)] selection: Option<RwSignal<BulkSelection>>,
 ) -> impl IntoView {
+    let id = item.id;
+    let owner_id = item.owner_id;
+    let auth = use_context::<AuthContext>(cx).unwrap();
+    let can_modify = move || Permission::ModifyItem { owner_id }.allows(auth.current_user().as_ref());
+    let removing = move || remove_item.input().with(|input| matches!(input, Some(input) if input.id as i64 == id));
+
     view! { cx,
-        <div>
+        <div class:pending-remove=removing>
+            {selection.map(|selection| view! { cx, <BulkSelectCheckbox selection=selection id=id/> })}
             <h3>{format!("{} [{}]", item.title, item.id)}</h3>
             <p>{item.description}</p>
             <A href={format!("/items/{}", item.id)}>"details"</A>
-            <ActionForm action=remove_item>
-                <input type="hidden" name="id" value={item.id}/>
-                <input type="submit" value="[x]"/>
-            </ActionForm>
+            {move || can_modify().then(|| view! { cx,
+                <CsrfActionForm action=remove_item>
+                    <input type="hidden" name="id" value={id}/>
+                    <input type="submit" value="[x]"/>
+                </CsrfActionForm>
+            })}
         </div>
     }
 }
@@ -2,9 +2,33 @@ use cfg_if::cfg_if;
 use leptos::*;
 
 pub mod app;
+pub mod auth;
+pub mod authz;
+pub mod bulk;
+pub mod csrf;
+pub mod dashboard;
+pub mod error_template;
+pub mod export;
+pub mod feed;
 pub mod file;
+pub mod health;
+pub mod history;
+pub mod import;
+pub mod item_events;
+pub mod item_filter;
+pub mod item_repository;
+pub mod item_store;
 pub mod items;
+pub mod monitoring;
+pub mod page_meta;
 pub mod pagination;
+pub mod rate_limit;
+pub mod rest_api;
+pub mod security_headers;
+pub mod server;
+pub mod sitemap;
+pub mod telemetry;
+pub mod trash;
 
 // Needs to be in lib.rs AFAIK because wasm-bindgen needs us to be compiling a lib. I may be wrong.
 cfg_if! {
@@ -3,73 +3,135 @@ use leptos::*;
 
 cfg_if! {
 if #[cfg(feature = "ssr")] {
-    use axum::{
-        routing::post,
-        extract::Extension,
-        Router,
-        body::{boxed, Body, BoxBody},
-        response::IntoResponse,
-        http::{Request, Response, StatusCode, Uri},
-    };
-    use axum::response::Response as AxumResponse;
-    use leptos_playground::app::{App, AppProps};
-    use leptos_playground::file::file_handler;
-    use leptos_axum::{generate_route_list, LeptosRoutes};
-    use std::sync::Arc;
-    use tower::ServiceExt;
-    use tower_http::services::ServeDir;
-    use leptos::{LeptosOptions};
-    use leptos_playground::items::register_server_functions;
+    use leptos_playground::auth::SessionConfig;
+    use leptos_playground::file::{fingerprint_pkg, precompress_dir};
+    use leptos_playground::trash::{self, DEFAULT_PURGE_AFTER_DAYS};
+    use leptos_playground::rate_limit::{RateLimitConfig, RateLimiter};
+    use leptos_playground::telemetry::init_tracing;
+    use std::net::SocketAddr;
+    use leptos_playground::monitoring::{self, metrics_router, DEFAULT_METRICS_ADDR};
+    use leptos_playground::item_repository::{repository, set_repository, RepositoryConfig};
+    use leptos_playground::items::existing_pool;
+    use leptos_playground::security_headers::SecurityHeadersConfig;
+    use leptos_playground::server::{app_router, register_server_functions};
+    use leptos_playground::sitemap::SitemapConfig;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
 
 
     #[tokio::main]
     async fn main() {
-        simple_logger::init_with_level(log::Level::Info).expect("couldn't initialize logging");
+        init_tracing();
+
+        let backend = RepositoryConfig::from_env()
+            .and_then(RepositoryConfig::repository)
+            .unwrap_or_else(|e| panic!("couldn't set up the item repository: {e}"));
+        _ = set_repository(backend);
+        repository()
+            .migrate()
+            .await
+            .unwrap_or_else(|e| panic!("couldn't migrate the item repository: {e}"));
 
         let conf = get_configuration(Some("Cargo.toml")).await.unwrap();
-        let leptos_options = conf.leptos_options;
+        let mut leptos_options = conf.leptos_options;
+        if let Err(e) = fingerprint_pkg(&mut leptos_options) {
+            tracing::warn!("Couldn't fingerprint the pkg files, they won't be cached for long: {}", e);
+        }
         let addr = leptos_options.site_address.clone();
-        let routes = generate_route_list(|cx| view! {cx, <App/> }).await;
 
         register_server_functions();
 
-        let app = Router::new()
-            .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
-            .leptos_routes(leptos_options.clone(), routes, |cx| view! {cx, <App/> })
-            .fallback(file_handler)
-            .layer(Extension(Arc::new(leptos_options)));
+        let purge_after_days = std::env::var("PURGE_DELETED_AFTER_DAYS")
+            .ok()
+            .and_then(|days| days.parse().ok())
+            .unwrap_or(DEFAULT_PURGE_AFTER_DAYS);
+        trash::spawn_purge_task(purge_after_days);
 
-        log!("Listening on {}", &addr);
-        axum::Server::bind(&addr)
-            .serve(app.into_make_service())
-            .await
-            .unwrap();
-    }
+        let site_root = leptos_options.site_root.clone();
+        tokio::task::spawn_blocking(move || match precompress_dir(Path::new(&site_root)) {
+            Ok(written) => tracing::info!("Precompressed {} static files in {}", written, site_root),
+            Err(e) => tracing::warn!("Couldn't precompress the static files in {}: {}", site_root, e),
+        });
 
-    pub async fn file_and_error_handler(uri: Uri, Extension(options): Extension<Arc<LeptosOptions>>, req: Request<Body>) -> AxumResponse {
-        let options = &*options;
-        let root = options.site_root.clone();
-        let res = get_static_file(uri.clone(), &root).await.unwrap();
+        let rate_limiter = RateLimiter::new(RateLimitConfig::from_env());
 
-        if res.status() == StatusCode::OK {
-           res.into_response()
-        } else{
-            let handler = leptos_axum::render_app_to_stream(options.to_owned(), |cx| view! {cx, <h1>"Error"</h1>} );//error_template(cx, None));
-            handler(req).await.into_response()
+        let metrics_handle = monitoring::install_recorder();
+        let metrics_addr: SocketAddr = std::env::var("METRICS_ADDR")
+            .ok()
+            .and_then(|addr| addr.parse().ok())
+            .unwrap_or_else(|| DEFAULT_METRICS_ADDR.parse().unwrap());
+        tokio::spawn(async move {
+            tracing::info!("Serving metrics on {}", &metrics_addr);
+            axum::Server::bind(&metrics_addr)
+                .serve(metrics_router(metrics_handle).into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let app = app_router(
+            leptos_options,
+            rate_limiter,
+            SecurityHeadersConfig::from_env(),
+            SitemapConfig::from_env(),
+            SessionConfig::from_env(),
+        )
+        .await;
+
+        let shutdown_timeout = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT_SECS));
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(());
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            tracing::info!("Shutting down, draining in-flight requests");
+            _ = shutdown_tx.send(());
+        });
+
+        tracing::info!("Listening on {}", &addr);
+        let server = axum::Server::bind(&addr)
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown({
+                let mut shutdown_rx = shutdown_rx.clone();
+                async move { _ = shutdown_rx.changed().await; }
+            });
+        // Long-lived requests like the item event stream would otherwise keep the server up forever.
+        let drain_deadline = async move {
+            _ = shutdown_rx.changed().await;
+            tokio::time::sleep(shutdown_timeout).await;
+        };
+        tokio::select! {
+            res = server => res.unwrap(),
+            _ = drain_deadline => tracing::warn!("Requests still running after {:?}, shutting down anyway", shutdown_timeout),
         }
+
+        if let Some(pool) = existing_pool() {
+            pool.close().await;
+        }
+        tracing::info!("Shut down");
     }
 
-    async fn get_static_file(uri: Uri, root: &str) -> Result<Response<BoxBody>, (StatusCode, String)> {
-        let req = Request::builder().uri(uri.clone()).body(Body::empty()).unwrap();
-        let root_path = format!("{root}");
-        // `ServeDir` implements `tower::Service` so we can call it with `tower::ServiceExt::oneshot`
-        // This path is relative to the cargo root
-        match ServeDir::new(&root_path).oneshot(req).await {
-            Ok(res) => Ok(res.map(boxed)),
-            Err(err) => Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Something went wrong: {}", err),
-            )),
+    async fn shutdown_signal() {
+        let ctrl_c = async {
+            tokio::signal::ctrl_c().await.expect("couldn't listen for ctrl-c");
+        };
+        #[cfg(unix)]
+        let terminate = async {
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("couldn't listen for SIGTERM")
+                .recv()
+                .await;
+        };
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = ctrl_c => {},
+            _ = terminate => {},
         }
     }
 } else {
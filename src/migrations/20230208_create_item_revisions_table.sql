@@ -0,0 +1,67 @@
+CREATE TABLE IF NOT EXISTS item_revisions
+(
+    id          INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    item_id     INTEGER  NOT NULL,
+    operation   VARCHAR  NOT NULL,
+    title       VARCHAR  NOT NULL,
+    description VARCHAR  NOT NULL,
+    changed_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS item_revisions_item_id ON item_revisions (item_id);
+
+CREATE TRIGGER IF NOT EXISTS item_revisions_no_update
+    BEFORE UPDATE ON item_revisions
+BEGIN
+    SELECT RAISE(ABORT, 'item_revisions is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS item_revisions_no_delete
+    BEFORE DELETE ON item_revisions
+BEGIN
+    SELECT RAISE(ABORT, 'item_revisions is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_revision_insert
+    AFTER INSERT ON items
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description)
+    VALUES (NEW.id, 'insert', NEW.title, NEW.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_revision_update
+    AFTER UPDATE OF title, description ON items
+    WHEN OLD.title IS NOT NEW.title OR OLD.description IS NOT NEW.description
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description)
+    VALUES (NEW.id, 'update', NEW.title, NEW.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_revision_delete
+    AFTER UPDATE OF deleted_at ON items
+    WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description)
+    VALUES (NEW.id, 'delete', NEW.title, NEW.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_revision_restore
+    AFTER UPDATE OF deleted_at ON items
+    WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description)
+    VALUES (NEW.id, 'restore', NEW.title, NEW.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_revision_purge
+    AFTER DELETE ON items
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description)
+    VALUES (OLD.id, 'purge', OLD.title, OLD.description);
+END;
+
+-- baseline revision for items that existed before revisions were recorded
+INSERT INTO item_revisions (item_id, operation, title, description)
+SELECT id, 'insert', title, description
+FROM items
+WHERE id NOT IN (SELECT item_id FROM item_revisions);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS users
+(
+    id            INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    username      VARCHAR  NOT NULL UNIQUE,
+    password_hash VARCHAR  NOT NULL,
+    created_at    DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS sessions
+(
+    id         VARCHAR  NOT NULL PRIMARY KEY,
+    user_id    INTEGER  NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_id ON sessions (user_id);
@@ -0,0 +1,9 @@
+-- New users can add items and edit their own ones, admins are promoted by hand:
+-- UPDATE users SET role = 'admin' WHERE username = '...';
+ALTER TABLE users ADD COLUMN role VARCHAR NOT NULL DEFAULT 'editor'
+    CHECK (role IN ('viewer', 'editor', 'admin'));
+
+-- Items created before accounts existed have no owner and can only be changed by admins.
+ALTER TABLE items ADD COLUMN owner_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS items_owner_id ON items (owner_id);
@@ -0,0 +1,3 @@
+-- Sessions started before this migration have no token, so their users have to log in again.
+DELETE FROM sessions;
+ALTER TABLE sessions ADD COLUMN csrf_token VARCHAR NOT NULL DEFAULT '';
@@ -0,0 +1,49 @@
+-- Who made a change. Triggers can't see the session, so every statement changing `items` sets
+-- `items.changed_by` to the acting user and the triggers copy it into the revision. NULL for changes
+-- made before this migration and for purges.
+ALTER TABLE items ADD COLUMN changed_by INTEGER REFERENCES users (id) ON DELETE SET NULL;
+ALTER TABLE item_revisions ADD COLUMN changed_by INTEGER REFERENCES users (id) ON DELETE SET NULL;
+
+DROP TRIGGER items_revision_insert;
+CREATE TRIGGER items_revision_insert
+    AFTER INSERT ON items
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+    VALUES (NEW.id, 'insert', NEW.title, NEW.description, NEW.changed_by);
+END;
+
+DROP TRIGGER items_revision_update;
+CREATE TRIGGER items_revision_update
+    AFTER UPDATE OF title, description ON items
+    WHEN OLD.title IS NOT NEW.title OR OLD.description IS NOT NEW.description
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+    VALUES (NEW.id, 'update', NEW.title, NEW.description, NEW.changed_by);
+END;
+
+DROP TRIGGER items_revision_delete;
+CREATE TRIGGER items_revision_delete
+    AFTER UPDATE OF deleted_at ON items
+    WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+    VALUES (NEW.id, 'delete', NEW.title, NEW.description, NEW.changed_by);
+END;
+
+DROP TRIGGER items_revision_restore;
+CREATE TRIGGER items_revision_restore
+    AFTER UPDATE OF deleted_at ON items
+    WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+    VALUES (NEW.id, 'restore', NEW.title, NEW.description, NEW.changed_by);
+END;
+
+-- Items are only purged by the background task, never by a user.
+DROP TRIGGER items_revision_purge;
+CREATE TRIGGER items_revision_purge
+    AFTER DELETE ON items
+BEGIN
+    INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+    VALUES (OLD.id, 'purge', OLD.title, OLD.description, NULL);
+END;
@@ -0,0 +1,86 @@
+-- Schema for `ITEM_BACKEND=postgres`, the same as Items.sqlite after all the migrations in
+-- `src/migrations`. Timestamps are read back in UTC, in SQLite's `CURRENT_TIMESTAMP` format.
+CREATE TABLE IF NOT EXISTS users
+(
+    id            BIGSERIAL   NOT NULL PRIMARY KEY,
+    username      VARCHAR     NOT NULL UNIQUE,
+    password_hash VARCHAR     NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    role          VARCHAR     NOT NULL DEFAULT 'editor' CHECK (role IN ('viewer', 'editor', 'admin'))
+);
+
+CREATE TABLE IF NOT EXISTS sessions
+(
+    id         VARCHAR     NOT NULL PRIMARY KEY,
+    user_id    BIGINT      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL,
+    csrf_token VARCHAR     NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_id ON sessions (user_id);
+
+CREATE TABLE IF NOT EXISTS items
+(
+    id          BIGSERIAL NOT NULL PRIMARY KEY,
+    title       VARCHAR   NOT NULL,
+    description VARCHAR   NOT NULL,
+    deleted_at  TIMESTAMPTZ,
+    owner_id    BIGINT REFERENCES users (id) ON DELETE SET NULL,
+    -- See 20230315_add_changed_by_to_item_revisions.sql.
+    changed_by  BIGINT REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS items_owner_id ON items (owner_id);
+
+-- No foreign key on `item_id`, the revisions of purged items are kept.
+CREATE TABLE IF NOT EXISTS item_revisions
+(
+    id          BIGSERIAL   NOT NULL PRIMARY KEY,
+    item_id     BIGINT      NOT NULL,
+    operation   VARCHAR     NOT NULL,
+    title       VARCHAR     NOT NULL,
+    description VARCHAR     NOT NULL,
+    changed_at  TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    changed_by  BIGINT REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS item_revisions_item_id ON item_revisions (item_id);
+
+CREATE OR REPLACE FUNCTION item_revisions_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'item_revisions is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER item_revisions_append_only
+    BEFORE UPDATE OR DELETE ON item_revisions
+    FOR EACH ROW EXECUTE FUNCTION item_revisions_append_only();
+
+CREATE OR REPLACE FUNCTION items_revision() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+        VALUES (NEW.id, 'insert', NEW.title, NEW.description, NEW.changed_by);
+    ELSIF TG_OP = 'DELETE' THEN
+        -- Items are only purged by the background task, never by a user.
+        INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+        VALUES (OLD.id, 'purge', OLD.title, OLD.description, NULL);
+        RETURN OLD;
+    ELSIF OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN
+        INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+        VALUES (NEW.id, 'delete', NEW.title, NEW.description, NEW.changed_by);
+    ELSIF OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN
+        INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+        VALUES (NEW.id, 'restore', NEW.title, NEW.description, NEW.changed_by);
+    ELSIF OLD.title IS DISTINCT FROM NEW.title OR OLD.description IS DISTINCT FROM NEW.description THEN
+        INSERT INTO item_revisions (item_id, operation, title, description, changed_by)
+        VALUES (NEW.id, 'update', NEW.title, NEW.description, NEW.changed_by);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER items_revision
+    AFTER INSERT OR UPDATE OR DELETE ON items
+    FOR EACH ROW EXECUTE FUNCTION items_revision();
@@ -0,0 +1,143 @@
+use cfg_if::cfg_if;
+
+pub const METRICS_PATH: &str = "/metrics";
+/// Address of the internal listener serving [METRICS_PATH], overridden by `METRICS_ADDR`.
+pub const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9100";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+        use std::time::Instant;
+
+        use axum::{
+            body::{boxed, Body, Bytes, HttpBody},
+            extract::{MatchedPath, State},
+            http::{header, HeaderMap, Request},
+            middleware::Next,
+            response::Response,
+            routing::get,
+            Router,
+        };
+        use metrics::{gauge, histogram, increment_counter};
+        use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+        use crate::items::existing_pool;
+
+        const LATENCY_BUCKETS: [f64; 11] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+        /// Installs the global Prometheus recorder, durations are exported as histograms.
+        pub fn install_recorder() -> PrometheusHandle {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_duration_seconds".to_string()), &LATENCY_BUCKETS)
+                .expect("invalid histogram buckets")
+                .install_recorder()
+                .expect("couldn't install the metrics recorder")
+        }
+
+        /// Router for the internal metrics listener, never merge it into the public app.
+        pub fn metrics_router(handle: PrometheusHandle) -> Router {
+            Router::new()
+                .route(METRICS_PATH, get(render_metrics))
+                .with_state(handle)
+        }
+
+        async fn render_metrics(State(handle): State<PrometheusHandle>) -> String {
+            if let Some(pool) = existing_pool() {
+                gauge!("db_pool_connections", pool.size() as f64);
+                gauge!("db_pool_idle_connections", pool.num_idle() as f64);
+            }
+            handle.render()
+        }
+
+        /// Middleware counting requests and their latency per route, per server function for
+        /// `/api/*fn_name`, and the time until `App` has finished streaming HTML pages.
+        pub async fn track_metrics(req: Request<Body>, next: Next<Body>) -> Response {
+            let start = Instant::now();
+            let method = req.method().to_string();
+            let route = req
+                .extensions()
+                .get::<MatchedPath>()
+                .map(|path| path.as_str().to_string())
+                .unwrap_or_else(|| "unmatched".to_string());
+            // Only registered names become labels, anything else would be unbounded cardinality.
+            let server_fn = req
+                .uri()
+                .path()
+                .strip_prefix("/api/")
+                .filter(|name| route == "/api/*fn_name" && leptos::server_fn_by_path(name).is_some())
+                .map(str::to_string);
+
+            let res = next.run(req).await;
+
+            let latency = start.elapsed().as_secs_f64();
+            let status = res.status().as_u16().to_string();
+            let labels = [("method", method), ("route", route.clone()), ("status", status.clone())];
+            increment_counter!("http_requests_total", &labels);
+            histogram!("http_request_duration_seconds", latency, &labels);
+
+            if let Some(server_fn) = server_fn {
+                let labels = [("server_fn", server_fn), ("status", status)];
+                increment_counter!("server_fn_calls_total", &labels);
+                histogram!("server_fn_duration_seconds", latency, &labels[..1]);
+            }
+            let is_html = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .map_or(false, |content_type| content_type.starts_with("text/html"));
+            // Pages are streamed, they are only rendered once the last chunk of the body is out.
+            if is_html {
+                let (parts, body) = res.into_parts();
+                return Response::from_parts(parts, boxed(RenderTimedBody { inner: body, start, route: Some(route) }));
+            }
+            res
+        }
+
+        /// Records `ssr_render_duration_seconds` for `route` when the streamed body has ended.
+        struct RenderTimedBody<B> {
+            inner: B,
+            start: Instant,
+            /// Taken once the duration is recorded.
+            route: Option<String>,
+        }
+
+        impl<B> HttpBody for RenderTimedBody<B>
+        where
+            B: HttpBody<Data = Bytes> + Unpin,
+        {
+            type Data = Bytes;
+            type Error = B::Error;
+
+            fn poll_data(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, B::Error>>> {
+                let poll = Pin::new(&mut self.inner).poll_data(cx);
+                if let Poll::Ready(None | Some(Err(_))) = poll {
+                    if let Some(route) = self.route.take() {
+                        histogram!("ssr_render_duration_seconds", self.start.elapsed().as_secs_f64(), "route" => route);
+                    }
+                }
+                poll
+            }
+
+            fn poll_trailers(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<Option<HeaderMap>, B::Error>> {
+                Pin::new(&mut self.inner).poll_trailers(cx)
+            }
+        }
+
+        /// Runs a database query, recording its duration under `query`.
+        pub async fn timed_query<T>(
+            query: &'static str,
+            fut: impl Future<Output = Result<T, sqlx::Error>>,
+        ) -> Result<T, sqlx::Error> {
+            let start = Instant::now();
+            let res = fut.await;
+            let outcome = if res.is_ok() { "ok" } else { "error" };
+            histogram!("db_query_duration_seconds", start.elapsed().as_secs_f64(), "query" => query, "outcome" => outcome);
+            res
+        }
+    }
+}
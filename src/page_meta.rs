@@ -0,0 +1,221 @@
+use std::rc::Rc;
+
+use cfg_if::cfg_if;
+use leptos::*;
+use leptos_meta::*;
+
+use crate::items::MockItem;
+
+pub const SITE_NAME: &str = "Leptos Playground";
+pub const SITE_DESCRIPTION: &str = "Leptos playground project.";
+/// Path of the item pages, rendered by `item_page_handler` instead of `leptos_routes`.
+pub const ITEM_PAGE_PATH: &str = "/items/:id";
+
+/// The item `item_page_handler` loaded, provided as context to the app it renders.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "ssr"), allow(dead_code))]
+struct PreloadedItem(Option<MockItem>);
+
+/// Header the Open Graph tags of a page are handed to [open_graph_tags] in, it doesn't leave the server.
+#[cfg_attr(not(feature = "ssr"), allow(dead_code))]
+const OPEN_GRAPH_HEADER: &str = "x-open-graph-tags";
+
+/// A static or reactive text prop of [PageMeta], like leptos_meta's `TextProp`, which can't be read.
+#[derive(Clone)]
+pub struct MetaText(Rc<dyn Fn() -> String>);
+
+impl MetaText {
+    pub fn get(&self) -> String {
+        (self.0)()
+    }
+}
+
+impl From<&str> for MetaText {
+    fn from(text: &str) -> Self {
+        let text = text.to_string();
+        MetaText(Rc::new(move || text.clone()))
+    }
+}
+
+impl From<String> for MetaText {
+    fn from(text: String) -> Self {
+        MetaText(Rc::new(move || text.clone()))
+    }
+}
+
+impl<F> From<F> for MetaText
+where
+    F: Fn() -> String + 'static,
+{
+    fn from(text: F) -> Self {
+        MetaText(Rc::new(text))
+    }
+}
+
+impl From<MetaText> for TextProp {
+    fn from(text: MetaText) -> Self {
+        TextProp::from(move || text.get())
+    }
+}
+
+/// `<title>`, description and the Open Graph and Twitter card tags of a page. The title is
+/// suffixed with [SITE_NAME] by the formatter of `App`.
+///
+/// `Meta` of leptos_meta 0.1 has no `property` attribute, so the `og:` tags are only rendered on
+/// the server, by [open_graph_tags]. Link preview crawlers don't run the app anyway.
+#[component]
+pub fn PageMeta(
+    cx: Scope,
+    #[prop(into)] title: MetaText,
+    #[prop(into)] description: MetaText,
+    /// `og:type`, `website` by default.
+    #[prop(optional, into)]
+    og_type: Option<MetaText>,
+) -> impl IntoView {
+    #[cfg(feature = "ssr")]
+    set_open_graph_tags(
+        cx,
+        &[
+            ("og:site_name", SITE_NAME.to_string()),
+            ("og:type", og_type.map_or_else(|| "website".to_string(), |og_type| og_type.get())),
+            ("og:title", title.get()),
+            ("og:description", description.get()),
+        ],
+    );
+    #[cfg(not(feature = "ssr"))]
+    _ = og_type;
+
+    view! { cx,
+        <Title text=title.clone()/>
+        <Meta name="description" content=description.clone()/>
+        <Meta name="twitter:card" content="summary"/>
+        <Meta name="twitter:title" content=title/>
+        <Meta name="twitter:description" content=description/>
+    }
+}
+
+/// The item `item_page_handler` loaded for the current request. The head is rendered with the
+/// shell, before any resource resolves, so this is what item pages take their meta tags from
+/// during SSR. Always `None` in the browser.
+pub fn preloaded_item(cx: Scope) -> Option<MockItem> {
+    use_context::<PreloadedItem>(cx).and_then(|preloaded| preloaded.0)
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::sync::Arc;
+
+        use axum::{
+            body::Body,
+            extract::{Extension, Path},
+            http::{HeaderName, HeaderValue, Request},
+            middleware::Next,
+            response::{IntoResponse, Response},
+        };
+
+        use crate::app::{App, AppProps};
+        use crate::item_store;
+        use crate::security_headers::{find_end, rewrite_body};
+
+        const HEAD_END: &[u8] = b"</head>";
+
+        /// Hands the `og:` `tags` of the page being rendered to [open_graph_tags].
+        fn set_open_graph_tags(cx: Scope, tags: &[(&str, String)]) {
+            // Only pages rendered by leptos_axum have a response, these are the ones that need the tags.
+            let Some(response) = use_context::<leptos_axum::ResponseOptions>(cx) else {
+                return;
+            };
+            let value = serde_urlencoded::to_string(tags)
+                .ok()
+                .and_then(|tags| HeaderValue::from_str(&tags).ok());
+            // The shell renders synchronously, nothing else holds the lock meanwhile.
+            match (value, response.0.try_write()) {
+                (Some(value), Ok(mut response)) => {
+                    response.insert_header(HeaderName::from_static(OPEN_GRAPH_HEADER), value)
+                }
+                _ => tracing::error!("could not set the Open Graph tags"),
+            }
+        }
+
+        /// Middleware writing the tags [PageMeta] handed over into the `<head>` of the page, as
+        /// `<meta property>` tags.
+        pub async fn open_graph_tags(req: Request<Body>, next: Next<Body>) -> Response {
+            let mut res = next.run(req).await;
+            let tags: Vec<(String, String)> = match res.headers_mut().remove(OPEN_GRAPH_HEADER) {
+                Some(tags) => match tags.to_str().ok().and_then(|tags| serde_urlencoded::from_str(tags).ok()) {
+                    Some(tags) => tags,
+                    None => {
+                        tracing::error!("invalid {OPEN_GRAPH_HEADER} header");
+                        return res;
+                    }
+                },
+                None => return res,
+            };
+            let tags = tags
+                .iter()
+                .map(|(property, content)| {
+                    format!(r#"<meta property="{}" content="{}"/>"#, escape_attr(property), escape_attr(content))
+                })
+                .collect::<String>();
+            rewrite_body(res, HeadTags { tags: Some(tags.into_bytes()), pending: Vec::new() }, HeadTags::push, HeadTags::finish)
+        }
+
+        /// Inserts `tags` before the first `</head>` of a page as it streams by.
+        struct HeadTags {
+            tags: Option<Vec<u8>>,
+            /// The end of the last chunk that may be the start of `</head>`.
+            pending: Vec<u8>,
+        }
+
+        impl HeadTags {
+            fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+                let tags = match self.tags.take() {
+                    Some(tags) => tags,
+                    None => return chunk.to_vec(),
+                };
+                let mut html = std::mem::take(&mut self.pending);
+                html.extend_from_slice(chunk);
+                match find_end(&html, HEAD_END) {
+                    Ok(len) => {
+                        let tail = html.split_off(len - HEAD_END.len());
+                        html.extend(tags);
+                        html.extend(tail);
+                        html
+                    }
+                    Err(len) => {
+                        self.pending = html.split_off(len);
+                        self.tags = Some(tags);
+                        html
+                    }
+                }
+            }
+
+            fn finish(self) -> Vec<u8> {
+                self.pending
+            }
+        }
+
+        /// Renders an item page like `leptos_routes` would, with the item loaded beforehand and
+        /// provided to [preloaded_item].
+        pub async fn item_page_handler(
+            Path(id): Path<String>,
+            Extension(options): Extension<Arc<LeptosOptions>>,
+            req: Request<Body>,
+        ) -> Response {
+            let item = match id.parse() {
+                Ok(id) => item_store::get(id).await.unwrap_or_else(|e| {
+                    tracing::warn!(id, error = %e, "failed to preload item");
+                    None
+                }),
+                Err(_) => None,
+            };
+
+            let handler = leptos_axum::render_app_to_stream_with_context(
+                (*options).clone(),
+                move |cx| provide_context(cx, PreloadedItem(item.clone())),
+                |cx| view! {cx, <App/> },
+            );
+            handler(req).await.into_response()
+        }
+    }
+}
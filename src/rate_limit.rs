@@ -0,0 +1,272 @@
+use cfg_if::cfg_if;
+use leptos::*;
+
+use crate::authz::AuthError;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::collections::HashMap;
+        use std::net::SocketAddr;
+        use std::sync::{Arc, Mutex};
+        use std::time::{Duration, Instant};
+
+        use axum::{
+            body::{Body, HttpBody},
+            extract::{ConnectInfo, State},
+            http::{header, Request, StatusCode},
+            middleware::Next,
+            response::{IntoResponse, Response},
+        };
+
+        use crate::auth::user_for_headers;
+        use crate::authz::server_fn_rejection;
+        use crate::csrf::is_read_only;
+
+        /// Buckets are dropped once there are this many and they have refilled completely.
+        const MAX_IDLE_BUCKETS: usize = 10_000;
+
+        #[derive(Clone, Copy, Debug)]
+        pub struct BucketConfig {
+            /// Calls allowed in a burst.
+            pub burst: u32,
+            pub per_minute: u32,
+        }
+
+        #[derive(Clone, Copy, Debug)]
+        pub struct RateLimitConfig {
+            pub reads: BucketConfig,
+            pub mutations: BucketConfig,
+            pub max_body_bytes: usize,
+            pub timeout: Duration,
+        }
+
+        impl Default for RateLimitConfig {
+            fn default() -> Self {
+                RateLimitConfig {
+                    reads: BucketConfig { burst: 60, per_minute: 120 },
+                    mutations: BucketConfig { burst: 10, per_minute: 30 },
+                    max_body_bytes: 64 * 1024,
+                    timeout: Duration::from_secs(10),
+                }
+            }
+        }
+
+        impl RateLimitConfig {
+            /// The defaults, overridden by `RATE_LIMIT_READ_BURST`, `RATE_LIMIT_READS_PER_MINUTE`,
+            /// `RATE_LIMIT_MUTATION_BURST`, `RATE_LIMIT_MUTATIONS_PER_MINUTE`, `MAX_REQUEST_BODY_BYTES`
+            /// and `REQUEST_TIMEOUT_SECS`.
+            pub fn from_env() -> Self {
+                fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+                    std::env::var(name).ok().and_then(|value| value.parse().ok())
+                }
+                let default = RateLimitConfig::default();
+                RateLimitConfig {
+                    reads: BucketConfig {
+                        burst: var("RATE_LIMIT_READ_BURST").unwrap_or(default.reads.burst),
+                        per_minute: var("RATE_LIMIT_READS_PER_MINUTE").unwrap_or(default.reads.per_minute),
+                    },
+                    mutations: BucketConfig {
+                        burst: var("RATE_LIMIT_MUTATION_BURST").unwrap_or(default.mutations.burst),
+                        per_minute: var("RATE_LIMIT_MUTATIONS_PER_MINUTE").unwrap_or(default.mutations.per_minute),
+                    },
+                    max_body_bytes: var("MAX_REQUEST_BODY_BYTES").unwrap_or(default.max_body_bytes),
+                    timeout: var("REQUEST_TIMEOUT_SECS")
+                        .map(Duration::from_secs)
+                        .unwrap_or(default.timeout),
+                }
+            }
+        }
+
+        struct TokenBucket {
+            tokens: f64,
+            updated: Instant,
+        }
+
+        impl TokenBucket {
+            fn refill(&mut self, config: BucketConfig, now: Instant) {
+                let refilled = now.duration_since(self.updated).as_secs_f64() * config.per_minute as f64 / 60.0;
+                self.tokens = (self.tokens + refilled).min(config.burst as f64);
+                self.updated = now;
+            }
+        }
+
+        /// Token buckets per client (user, or IP address when not logged in) and kind of call.
+        pub struct RateLimiter {
+            config: RateLimitConfig,
+            buckets: Mutex<HashMap<(String, bool), TokenBucket>>,
+        }
+
+        impl RateLimiter {
+            pub fn new(config: RateLimitConfig) -> Arc<Self> {
+                Arc::new(RateLimiter {
+                    config,
+                    buckets: Mutex::new(HashMap::new()),
+                })
+            }
+
+            /// Takes a token from the client's bucket, or returns how long to wait for the next one.
+            fn acquire(&self, client: String, read_only: bool) -> Result<(), Duration> {
+                let config = if read_only { self.config.reads } else { self.config.mutations };
+                let now = Instant::now();
+                let mut buckets = self.buckets.lock().unwrap();
+                if buckets.len() >= MAX_IDLE_BUCKETS {
+                    buckets.retain(|(_, read_only), bucket| {
+                        let config = if *read_only { self.config.reads } else { self.config.mutations };
+                        bucket.refill(config, now);
+                        bucket.tokens < config.burst as f64
+                    });
+                }
+
+                let bucket = buckets.entry((client, read_only)).or_insert(TokenBucket {
+                    tokens: config.burst as f64,
+                    updated: now,
+                });
+                bucket.refill(config, now);
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    Ok(())
+                } else {
+                    let wait = (1.0 - bucket.tokens) * 60.0 / config.per_minute.max(1) as f64;
+                    Err(Duration::from_secs_f64(wait))
+                }
+            }
+        }
+
+        /// The user of a valid session, else the IP address. A made up session cookie doesn't get
+        /// a client its own buckets.
+        async fn client_key(req: &Request<Body>) -> String {
+            match user_for_headers(req.headers()).await {
+                Ok(Some(user)) => return format!("user:{}", user.id),
+                Ok(None) => {}
+                Err(e) => log::warn!("Rate limiting by IP, couldn't look up the session: {e}"),
+            }
+            req.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| format!("ip:{}", addr.ip()))
+                .unwrap_or_else(|| "unknown".to_string())
+        }
+
+        impl Rejection {
+            /// 429 when over the rate limit, 413 for a body that is too large and 504 when the
+            /// server didn't answer in time.
+            pub fn status_code(&self) -> StatusCode {
+                match self {
+                    Rejection::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+                    Rejection::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                    Rejection::Timeout => StatusCode::GATEWAY_TIMEOUT,
+                }
+            }
+        }
+
+        impl IntoResponse for Rejection {
+            fn into_response(self) -> Response {
+                server_fn_rejection(self.status_code(), self.code())
+            }
+        }
+
+        /// Middleware for `/api/*fn_name`: rate limits reads and mutations separately, rejects
+        /// bodies over `max_body_bytes` and gives up on calls that take longer than `timeout`. The
+        /// calls turned away get the [Rejection::status_code] with the [Rejection::code] as the
+        /// status text, and a `Retry-After` when over the rate limit.
+        pub async fn rate_limit(
+            State(limiter): State<Arc<RateLimiter>>,
+            req: Request<Body>,
+            next: Next<Body>,
+        ) -> Response {
+            let config = limiter.config;
+            let read_only = is_read_only(req.uri().path());
+            if let Err(retry_after) = limiter.acquire(client_key(&req).await, read_only) {
+                let retry_after = (retry_after.as_secs_f64().ceil() as u64).max(1).to_string();
+                return ([(header::RETRY_AFTER, retry_after)], Rejection::TooManyRequests).into_response();
+            }
+
+            let content_length = req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|len| len.to_str().ok())
+                .and_then(|len| len.parse::<usize>().ok());
+            if content_length.map_or(false, |len| len > config.max_body_bytes) {
+                return Rejection::PayloadTooLarge.into_response();
+            }
+            // Content-Length can be missing or wrong, so count what is actually read as well.
+            let (parts, mut body) = req.into_parts();
+            let mut bytes = Vec::new();
+            while let Some(chunk) = body.data().await {
+                match chunk {
+                    Ok(chunk) if bytes.len() + chunk.len() <= config.max_body_bytes => bytes.extend_from_slice(&chunk),
+                    Ok(_) => return Rejection::PayloadTooLarge.into_response(),
+                    Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+                }
+            }
+
+            let req = Request::from_parts(parts, Body::from(bytes));
+            match tokio::time::timeout(config.timeout, next.run(req)).await {
+                Ok(res) => res,
+                Err(_) => Rejection::Timeout.into_response(),
+            }
+        }
+    }
+}
+
+/// Why `rate_limit` turned a server function call away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    TooManyRequests,
+    /// The body is over `max_body_bytes`.
+    PayloadTooLarge,
+    /// The server took longer than `timeout` to answer, the client sent its request in time.
+    Timeout,
+}
+
+impl Rejection {
+    /// The status text of the rejected call, like [AuthError::code].
+    pub fn code(&self) -> &'static str {
+        match self {
+            Rejection::TooManyRequests => "too_many_requests",
+            Rejection::PayloadTooLarge => "payload_too_large",
+            Rejection::Timeout => "timeout",
+        }
+    }
+
+    /// Whether a failed call with HTTP status `status` is answered with a [Rejection::code] as its
+    /// status text, which only the 5xx are in the leptos client.
+    pub fn has_status(status: u16) -> bool {
+        matches!(status, 413 | 429 | 504)
+    }
+
+    /// The [Rejection] of a call, `None` if it failed for another reason.
+    pub fn from_server_fn_error(e: &ServerFnError) -> Option<Self> {
+        match e {
+            ServerFnError::ServerError(message) => {
+                [Rejection::TooManyRequests, Rejection::PayloadTooLarge, Rejection::Timeout]
+                    .into_iter()
+                    .find(|rejection| rejection.code() == message)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Message for a failed server function call, from what the browser client gets of it: the status
+/// text of a 5xx or of a [Rejection] as a `ServerError`, which is an [AuthError::code], a
+/// [Rejection::code], a validation message or `authz::INTERNAL_ERROR`. Other rejections, like an
+/// expired CSRF token, are 4xx the client can't decode, they arrive as `Deserialization` errors.
+pub fn describe_error(e: &ServerFnError) -> String {
+    if let Some(e) = AuthError::from_server_fn_error(e) {
+        return match e {
+            AuthError::LoginRequired => "please log in first".to_string(),
+            AuthError::Forbidden => "you are not allowed to do that".to_string(),
+            AuthError::NotFound => "it is no longer there, reload the page".to_string(),
+        };
+    }
+    match (Rejection::from_server_fn_error(e), e) {
+        (Some(Rejection::TooManyRequests), _) => "too many requests, try again in a moment".to_string(),
+        (Some(Rejection::PayloadTooLarge), _) => "too much data in one request".to_string(),
+        (Some(Rejection::Timeout), _) => "the server took too long to answer".to_string(),
+        (None, ServerFnError::Deserialization(_)) => {
+            "the server rejected the request, reload the page and try again".to_string()
+        }
+        (None, ServerFnError::ServerError(message)) => message.clone(),
+        (None, e) => e.to_string(),
+    }
+}
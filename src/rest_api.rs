@@ -0,0 +1,310 @@
+use cfg_if::cfg_if;
+
+pub const API_V1_PREFIX: &str = "/api/v1";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use axum::{
+            extract::{
+                rejection::{JsonRejection, PathRejection, QueryRejection},
+                Path, Query,
+            },
+            http::{header, HeaderMap, StatusCode},
+            response::{IntoResponse, Response},
+            routing::get,
+            Json, Router,
+        };
+        use leptos::ServerFnError;
+        use serde::{Deserialize, Serialize};
+        use utoipa::{IntoParams, OpenApi, ToSchema};
+
+        use crate::auth::{user_for_headers, User};
+        use crate::authz::{AuthError, Permission};
+        use crate::item_filter::{ItemFilter, ItemSort};
+        use crate::item_repository::RepositoryError;
+        use crate::item_store;
+        use crate::items::{validate_item, MockItem};
+        use crate::pagination::{DEFAULT_PAGE, DEFAULT_PAGE_SIZE};
+
+        const MAX_PAGE_SIZE: u32 = 100;
+
+        #[derive(OpenApi)]
+        #[openapi(
+            paths(list_items, get_item, create_item, patch_item, delete_item),
+            components(schemas(MockItem, ItemSort, ItemPage, NewItem, ItemPatch, ApiErrorResponse, ApiErrorBody))
+        )]
+        pub struct ApiDoc;
+
+        /// Routes of the versioned REST API, mounted next to the server functions under `/api`.
+        pub fn router() -> Router {
+            Router::new()
+                .route("/api/v1/items", get(list_items).post(create_item))
+                .route(
+                    "/api/v1/items/:id",
+                    get(get_item).patch(patch_item).delete(delete_item),
+                )
+                .route("/api/v1/openapi.json", get(openapi_json))
+        }
+
+        async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+            Json(ApiDoc::openapi())
+        }
+
+        #[derive(Debug, Serialize, ToSchema)]
+        pub struct ApiErrorBody {
+            status: u16,
+            message: String,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            details: Vec<String>,
+        }
+
+        /// JSON body of every error response of the REST API.
+        #[derive(Debug, Serialize, ToSchema)]
+        pub struct ApiErrorResponse {
+            error: ApiErrorBody,
+        }
+
+        #[derive(Debug)]
+        pub struct ApiError {
+            status: StatusCode,
+            error: ApiErrorBody,
+        }
+
+        impl ApiError {
+            fn new(status: StatusCode, message: impl Into<String>) -> Self {
+                ApiError {
+                    status,
+                    error: ApiErrorBody {
+                        status: status.as_u16(),
+                        message: message.into(),
+                        details: vec![],
+                    },
+                }
+            }
+            fn not_found(id: i64) -> Self {
+                ApiError::new(StatusCode::NOT_FOUND, format!("item {id} not found"))
+            }
+            fn validation(details: Vec<String>) -> Self {
+                let mut err = ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid item");
+                err.error.details = details;
+                err
+            }
+        }
+
+        impl From<RepositoryError> for ApiError {
+            fn from(e: RepositoryError) -> Self {
+                log::error!("REST API database error: {e}");
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+            }
+        }
+
+        impl From<AuthError> for ApiError {
+            fn from(e: AuthError) -> Self {
+                ApiError::new(e.status_code(), e.to_string())
+            }
+        }
+
+        impl From<ServerFnError> for ApiError {
+            fn from(e: ServerFnError) -> Self {
+                log::error!("REST API error: {e}");
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+            }
+        }
+
+        /// The user of the request's session cookie, if they have `permission`.
+        async fn authorize(headers: &HeaderMap, permission: Permission) -> Result<Option<User>, ApiError> {
+            let user = user_for_headers(headers).await?;
+            permission.check(user.as_ref())?;
+            Ok(user)
+        }
+
+        /// Checks that the user may modify item `id`, 404 if there is no such item.
+        async fn authorize_item(headers: &HeaderMap, id: i64) -> Result<Option<User>, ApiError> {
+            let owner_id = item_store::owner_id(id)
+                .await?
+                .ok_or_else(|| ApiError::not_found(id))?;
+            authorize(headers, Permission::ModifyItem { owner_id }).await
+        }
+
+        macro_rules! impl_from_rejection {
+            ($($rejection:ty),*) => {$(
+                impl From<$rejection> for ApiError {
+                    fn from(rejection: $rejection) -> Self {
+                        ApiError::new(rejection.status(), rejection.body_text())
+                    }
+                }
+            )*};
+        }
+        impl_from_rejection!(JsonRejection, PathRejection, QueryRejection);
+
+        impl IntoResponse for ApiError {
+            fn into_response(self) -> Response {
+                (self.status, Json(ApiErrorResponse { error: self.error })).into_response()
+            }
+        }
+
+        #[derive(Debug, Deserialize, IntoParams)]
+        #[into_params(parameter_in = Query)]
+        pub struct ListParams {
+            /// 1-based page number
+            page: Option<u32>,
+            /// items per page, at most 100
+            page_size: Option<u32>,
+            /// search in title and description
+            q: Option<String>,
+            sort: Option<ItemSort>,
+        }
+
+        #[derive(Debug, Serialize, ToSchema)]
+        pub struct ItemPage {
+            items: Vec<MockItem>,
+            page: u32,
+            page_size: u32,
+            total: u32,
+        }
+
+        #[derive(Debug, Deserialize, ToSchema)]
+        pub struct NewItem {
+            title: String,
+            #[serde(default)]
+            description: String,
+        }
+
+        #[derive(Debug, Deserialize, ToSchema)]
+        pub struct ItemPatch {
+            title: Option<String>,
+            description: Option<String>,
+        }
+
+        #[utoipa::path(
+            get,
+            path = "/api/v1/items",
+            params(ListParams),
+            responses((status = 200, description = "One page of matching items", body = ItemPage))
+        )]
+        async fn list_items(
+            params: Result<Query<ListParams>, QueryRejection>,
+        ) -> Result<Json<ItemPage>, ApiError> {
+            let Query(params) = params?;
+            let page = params.page.unwrap_or(DEFAULT_PAGE as u32).max(1);
+            let page_size = params
+                .page_size
+                .unwrap_or(DEFAULT_PAGE_SIZE as u32)
+                .clamp(1, MAX_PAGE_SIZE);
+            let filter = ItemFilter {
+                q: params.q.filter(|q| !q.trim().is_empty()),
+                sort: params.sort.unwrap_or_default(),
+            };
+
+            let (items, total) = item_store::list(&filter, page, page_size).await?;
+            Ok(Json(ItemPage {
+                items,
+                page,
+                page_size,
+                total,
+            }))
+        }
+
+        #[utoipa::path(
+            get,
+            path = "/api/v1/items/{id}",
+            params(("id" = i64, Path, description = "item id")),
+            responses(
+                (status = 200, body = MockItem),
+                (status = 404, body = ApiErrorResponse)
+            )
+        )]
+        async fn get_item(id: Result<Path<i64>, PathRejection>) -> Result<Json<MockItem>, ApiError> {
+            let Path(id) = id?;
+            item_store::get(id)
+                .await?
+                .map(Json)
+                .ok_or_else(|| ApiError::not_found(id))
+        }
+
+        #[utoipa::path(
+            post,
+            path = "/api/v1/items",
+            request_body = NewItem,
+            responses(
+                (status = 201, body = MockItem),
+                (status = 401, body = ApiErrorResponse),
+                (status = 403, body = ApiErrorResponse),
+                (status = 422, body = ApiErrorResponse)
+            )
+        )]
+        async fn create_item(
+            headers: HeaderMap,
+            new_item: Result<Json<NewItem>, JsonRejection>,
+        ) -> Result<impl IntoResponse, ApiError> {
+            let user = authorize(&headers, Permission::CreateItem).await?;
+            let Json(new_item) = new_item?;
+            validate_item(&new_item.title, &new_item.description).map_err(ApiError::validation)?;
+
+            let owner_id = user.map(|user| user.id);
+            let item = item_store::insert(new_item.title, new_item.description, owner_id).await?;
+            let location = format!("{API_V1_PREFIX}/items/{}", item.id);
+            Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(item)))
+        }
+
+        #[utoipa::path(
+            patch,
+            path = "/api/v1/items/{id}",
+            params(("id" = i64, Path, description = "item id")),
+            request_body = ItemPatch,
+            responses(
+                (status = 200, body = MockItem),
+                (status = 401, body = ApiErrorResponse),
+                (status = 403, body = ApiErrorResponse),
+                (status = 404, body = ApiErrorResponse),
+                (status = 422, body = ApiErrorResponse)
+            )
+        )]
+        async fn patch_item(
+            headers: HeaderMap,
+            id: Result<Path<i64>, PathRejection>,
+            patch: Result<Json<ItemPatch>, JsonRejection>,
+        ) -> Result<Json<MockItem>, ApiError> {
+            let Path(id) = id?;
+            let user = authorize_item(&headers, id).await?;
+            let Json(patch) = patch?;
+            let current = item_store::get(id)
+                .await?
+                .ok_or_else(|| ApiError::not_found(id))?;
+            validate_item(
+                patch.title.as_ref().unwrap_or(&current.title),
+                patch.description.as_ref().unwrap_or(&current.description),
+            )
+            .map_err(ApiError::validation)?;
+
+            item_store::update(id, patch.title, patch.description, user.map(|user| user.id))
+                .await?
+                .map(Json)
+                .ok_or_else(|| ApiError::not_found(id))
+        }
+
+        #[utoipa::path(
+            delete,
+            path = "/api/v1/items/{id}",
+            params(("id" = i64, Path, description = "item id")),
+            responses(
+                (status = 204, description = "Item moved to the trash"),
+                (status = 401, body = ApiErrorResponse),
+                (status = 403, body = ApiErrorResponse),
+                (status = 404, body = ApiErrorResponse)
+            )
+        )]
+        async fn delete_item(
+            headers: HeaderMap,
+            id: Result<Path<i64>, PathRejection>,
+        ) -> Result<StatusCode, ApiError> {
+            let Path(id) = id?;
+            let user = authorize_item(&headers, id).await?;
+            if item_store::soft_delete(id, user.map(|user| user.id)).await? {
+                Ok(StatusCode::NO_CONTENT)
+            } else {
+                Err(ApiError::not_found(id))
+            }
+        }
+    }
+}
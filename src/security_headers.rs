@@ -0,0 +1,349 @@
+use cfg_if::cfg_if;
+
+pub const CSP_REPORT_PATH: &str = "/csp-report";
+
+/// `{nonce}` is replaced by the nonce of each response. `'wasm-unsafe-eval'` lets the hydration
+/// script instantiate the app's wasm, `'self'` covers the JS it imports from `/pkg`.
+pub const DEFAULT_CSP: &str = "default-src 'self'; script-src 'self' 'nonce-{nonce}' 'wasm-unsafe-eval'; \
+    style-src 'self'; img-src 'self' data:; connect-src 'self'; object-src 'none'; base-uri 'self'; \
+    form-action 'self'";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use axum::{
+            body::{boxed, Body, Bytes, HttpBody, StreamBody},
+            extract::State,
+            http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode},
+            middleware::Next,
+            response::Response,
+            routing::post,
+            Router,
+        };
+        use leptos::LeptosOptions;
+        use metrics::increment_counter;
+        use rand_core::{OsRng, RngCore};
+
+        /// Opening tags of the scripts leptos_axum writes into a page: the hydration script, the
+        /// live reload script and the ones streaming the resources and `<Suspense/>` fragments.
+        const NONCE_TAGS: [&[u8]; 3] = [b"<script>", b"<script type=\"module\">", b"<script crossorigin=\"\">"];
+        /// Elements whose contents are text up to their end tag, where `<script>` is no tag.
+        const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "title"];
+        const COMMENT_START: &[u8] = b"<!--";
+        const COMMENT_END: &str = "-->";
+
+        #[derive(Clone, Debug)]
+        pub struct SecurityHeadersConfig {
+            /// The policy without `frame-ancestors` and `report-uri`, see [DEFAULT_CSP].
+            pub csp: String,
+            pub frame_ancestors: String,
+            /// Only report violations to [CSP_REPORT_PATH] instead of blocking them.
+            pub report_only: bool,
+            /// `None` leaves out `Strict-Transport-Security`, only turn it on when served over HTTPS.
+            pub hsts_max_age: Option<Duration>,
+            /// Origin of the live reload websocket of `cargo leptos watch`, allowed in `connect-src`.
+            pub live_reload: Option<String>,
+        }
+
+        impl Default for SecurityHeadersConfig {
+            fn default() -> Self {
+                SecurityHeadersConfig {
+                    csp: DEFAULT_CSP.to_string(),
+                    frame_ancestors: "'none'".to_string(),
+                    report_only: false,
+                    hsts_max_age: None,
+                    live_reload: None,
+                }
+            }
+        }
+
+        impl SecurityHeadersConfig {
+            /// The defaults, overridden by `CONTENT_SECURITY_POLICY`, `CSP_FRAME_ANCESTORS`,
+            /// `CSP_REPORT_ONLY=true` and `HSTS_MAX_AGE_SECS`, which turns HSTS on unless it is `0`.
+            pub fn from_env() -> Self {
+                let default = SecurityHeadersConfig::default();
+                SecurityHeadersConfig {
+                    csp: std::env::var("CONTENT_SECURITY_POLICY").unwrap_or(default.csp),
+                    frame_ancestors: std::env::var("CSP_FRAME_ANCESTORS").unwrap_or(default.frame_ancestors),
+                    report_only: std::env::var("CSP_REPORT_ONLY").map_or(false, |v| v == "true" || v == "1"),
+                    hsts_max_age: std::env::var("HSTS_MAX_AGE_SECS")
+                        .ok()
+                        .and_then(|secs| secs.parse().ok())
+                        .filter(|secs| *secs > 0)
+                        .map(Duration::from_secs),
+                    live_reload: default.live_reload,
+                }
+            }
+
+            /// Allows the live reload websocket when `LEPTOS_WATCH` is set, as leptos_axum only
+            /// adds its script to the pages then.
+            pub fn with_live_reload(mut self, options: &LeptosOptions) -> Self {
+                if std::env::var("LEPTOS_WATCH").is_ok() {
+                    self.live_reload = Some(format!("ws://{}:{}", options.site_address.ip(), options.reload_port));
+                }
+                self
+            }
+
+            fn policy(&self, nonce: &str) -> String {
+                let mut csp = self.csp.replace("{nonce}", nonce);
+                if let Some(origin) = &self.live_reload {
+                    csp = with_connect_src(&csp, origin);
+                }
+                format!("{csp}; frame-ancestors {}; report-uri {CSP_REPORT_PATH}", self.frame_ancestors)
+            }
+        }
+
+        /// `csp` with `origin` added to its `connect-src`, or with a `connect-src` allowing it and
+        /// `'self'` if it has none.
+        fn with_connect_src(csp: &str, origin: &str) -> String {
+            let mut found = false;
+            let mut directives: Vec<String> = csp
+                .split(';')
+                .map(str::trim)
+                .filter(|directive| !directive.is_empty())
+                .map(|directive| {
+                    if directive.split_whitespace().next() == Some("connect-src") {
+                        found = true;
+                        format!("{directive} {origin}")
+                    } else {
+                        directive.to_string()
+                    }
+                })
+                .collect();
+            if !found {
+                directives.push(format!("connect-src 'self' {origin}"));
+            }
+            directives.join("; ")
+        }
+
+        fn random_nonce() -> String {
+            let mut bytes = [0u8; 16];
+            OsRng.fill_bytes(&mut bytes);
+            bytes.iter().map(|b| format!("{b:02x}")).collect()
+        }
+
+        /// Middleware setting the security headers on every response, with a fresh nonce in the
+        /// policy. Pages get the nonce on their scripts through [ScriptNonces].
+        pub async fn security_headers(
+            State(config): State<Arc<SecurityHeadersConfig>>,
+            req: Request<Body>,
+            next: Next<Body>,
+        ) -> Response {
+            let nonce = random_nonce();
+            let is_get = matches!(*req.method(), Method::GET | Method::HEAD);
+            let mut res = next.run(req).await;
+            if is_get && is_page(res.headers()) {
+                res = with_script_nonces(res, &nonce);
+            }
+
+            let csp_header = if config.report_only {
+                header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+            } else {
+                header::CONTENT_SECURITY_POLICY
+            };
+            let headers = res.headers_mut();
+            insert(headers, csp_header, &config.policy(&nonce));
+            insert(headers, header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+            insert(headers, header::REFERRER_POLICY, "strict-origin-when-cross-origin");
+            if let Some(max_age) = config.hsts_max_age {
+                insert(headers, header::STRICT_TRANSPORT_SECURITY, &format!("max-age={}", max_age.as_secs()));
+            }
+            res
+        }
+
+        /// Whether a response is an uncompressed HTML page. leptos_axum sends its pages without a
+        /// `Content-Type`.
+        fn is_page(headers: &HeaderMap) -> bool {
+            !headers.contains_key(header::CONTENT_ENCODING)
+                && headers.get(header::CONTENT_TYPE).map_or(true, |content_type| {
+                    content_type.to_str().map_or(false, |content_type| content_type.starts_with("text/html"))
+                })
+        }
+
+        /// Streams the body of `res` through [ScriptNonces]. Its length changes, and the validators
+        /// of a static page would revalidate a copy with a nonce that is no longer accepted.
+        fn with_script_nonces(mut res: Response, nonce: &str) -> Response {
+            for name in [header::ETAG, header::LAST_MODIFIED] {
+                res.headers_mut().remove(name);
+            }
+            rewrite_body(res, ScriptNonces::new(nonce), ScriptNonces::push, ScriptNonces::finish)
+        }
+
+        /// `res` with each chunk of its body passed to `push` along with `state`, and what `finish`
+        /// returns from it appended at the end. Drops the `Content-Length`, which no longer holds.
+        pub(crate) fn rewrite_body<S: Send + 'static>(
+            res: Response,
+            state: S,
+            push: fn(&mut S, &[u8]) -> Vec<u8>,
+            finish: fn(S) -> Vec<u8>,
+        ) -> Response {
+            let (mut parts, body) = res.into_parts();
+            parts.headers.remove(header::CONTENT_LENGTH);
+            let chunks = futures::stream::unfold(Some((body, state)), move |next| async move {
+                let (mut body, mut state) = next?;
+                match body.data().await {
+                    Some(Ok(chunk)) => {
+                        let chunk = push(&mut state, &chunk);
+                        Some((Ok(Bytes::from(chunk)), Some((body, state))))
+                    }
+                    Some(Err(e)) => Some((Err(e), None)),
+                    None => Some((Ok(Bytes::from(finish(state))), None)),
+                }
+            });
+            Response::from_parts(parts, boxed(StreamBody::new(chunks)))
+        }
+
+        #[derive(Clone, Copy, Debug)]
+        enum Markup {
+            Text,
+            /// In an element of [RAW_TEXT_ELEMENTS].
+            RawText(&'static str),
+            Comment,
+        }
+
+        /// Puts a nonce on the scripts of a page as it streams by, chunk by chunk. Only the whole
+        /// opening tags in [NONCE_TAGS] get one. The contents of scripts, styles, titles, text areas
+        /// and comments are skipped, the `<script>` of an item's title is no tag there. Leptos
+        /// escapes `<` in text and attribute values, so the app's data can't add a tag elsewhere.
+        pub struct ScriptNonces {
+            nonce_attr: Vec<u8>,
+            markup: Markup,
+            /// The end of the last chunk that may be the start of a tag, an end tag or a comment.
+            pending: Vec<u8>,
+        }
+
+        impl ScriptNonces {
+            pub fn new(nonce: &str) -> Self {
+                ScriptNonces {
+                    nonce_attr: format!(" nonce=\"{nonce}\"").into_bytes(),
+                    markup: Markup::Text,
+                    pending: Vec::new(),
+                }
+            }
+
+            /// The next `chunk` of the page with the nonces added, without what is held back until
+            /// the next chunk shows where it ends.
+            pub fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+                let mut html = std::mem::take(&mut self.pending);
+                html.extend_from_slice(chunk);
+                let mut out = Vec::with_capacity(html.len());
+                let mut pos = 0;
+                while pos < html.len() {
+                    let rest = &html[pos..];
+                    let end = match self.markup {
+                        Markup::Text => None,
+                        Markup::RawText(element) => Some(format!("</{element}")),
+                        Markup::Comment => Some(COMMENT_END.to_string()),
+                    };
+                    let consumed = match end {
+                        None => match rest.iter().position(|&b| b == b'<') {
+                            None => {
+                                out.extend_from_slice(rest);
+                                rest.len()
+                            }
+                            Some(start) => {
+                                out.extend_from_slice(&rest[..start]);
+                                match self.tag(&rest[start..], &mut out) {
+                                    Some(len) => start + len,
+                                    None => {
+                                        self.pending = rest[start..].to_vec();
+                                        break;
+                                    }
+                                }
+                            }
+                        },
+                        Some(end) => match find_end(rest, end.as_bytes()) {
+                            Ok(len) => {
+                                out.extend_from_slice(&rest[..len]);
+                                self.markup = Markup::Text;
+                                len
+                            }
+                            Err(len) => {
+                                out.extend_from_slice(&rest[..len]);
+                                self.pending = rest[len..].to_vec();
+                                break;
+                            }
+                        },
+                    };
+                    pos += consumed;
+                }
+                out
+            }
+
+            /// What is left at the end of the page.
+            pub fn finish(self) -> Vec<u8> {
+                self.pending
+            }
+
+            /// Writes the markup `html` starts with to `out`, returning its length, or `None` if it
+            /// may go on in the next chunk.
+            fn tag(&mut self, html: &[u8], out: &mut Vec<u8>) -> Option<usize> {
+                if html.starts_with(COMMENT_START) {
+                    out.extend_from_slice(COMMENT_START);
+                    self.markup = Markup::Comment;
+                    return Some(COMMENT_START.len());
+                }
+                if html.len() < COMMENT_START.len() && COMMENT_START.starts_with(html) {
+                    return None;
+                }
+                if !html[1].is_ascii_alphabetic() {
+                    out.push(b'<');
+                    return Some(1);
+                }
+                let end = html.iter().position(|&b| b == b'>')?;
+                let tag = &html[..=end];
+                if NONCE_TAGS.iter().any(|nonce_tag| *nonce_tag == tag) {
+                    out.extend_from_slice(b"<script");
+                    out.extend_from_slice(&self.nonce_attr);
+                    out.extend_from_slice(&tag[b"<script".len()..]);
+                } else {
+                    out.extend_from_slice(tag);
+                }
+                let name: Vec<u8> = tag[1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_alphanumeric())
+                    .map(u8::to_ascii_lowercase)
+                    .collect();
+                if let Some(element) = RAW_TEXT_ELEMENTS.iter().find(|element| element.as_bytes() == name.as_slice()) {
+                    self.markup = Markup::RawText(*element);
+                }
+                Some(tag.len())
+            }
+        }
+
+        /// The length of `html` up to and including `end`, ignoring ASCII case, or if it isn't
+        /// there, the length of what can't be the start of it.
+        pub(crate) fn find_end(html: &[u8], end: &[u8]) -> Result<usize, usize> {
+            if let Some(at) = html.windows(end.len()).position(|window| window.eq_ignore_ascii_case(end)) {
+                return Ok(at + end.len());
+            }
+            let held = (1..end.len())
+                .rev()
+                .find(|&len| len <= html.len() && html[html.len() - len..].eq_ignore_ascii_case(&end[..len]))
+                .unwrap_or(0);
+            Err(html.len() - held)
+        }
+
+        fn insert(headers: &mut HeaderMap, name: HeaderName, value: &str) {
+            match HeaderValue::from_str(value) {
+                Ok(value) => {
+                    headers.insert(name, value);
+                }
+                Err(_) => tracing::error!("invalid {} header: {}", name, value),
+            }
+        }
+
+        pub fn router() -> Router {
+            Router::new().route(CSP_REPORT_PATH, post(csp_report))
+        }
+
+        /// Collects the violation reports browsers send to `report-uri`, for tuning the policy.
+        async fn csp_report(body: Bytes) -> StatusCode {
+            increment_counter!("csp_violations_total");
+            tracing::warn!(report = %String::from_utf8_lossy(&body), "CSP violation");
+            StatusCode::NO_CONTENT
+        }
+    }
+}
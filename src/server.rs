@@ -0,0 +1,106 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        // The axum app, shared by `main.rs` and the integration tests in `tests/`.
+        use std::sync::Arc;
+
+        use axum::{
+            body::Body,
+            extract::{Extension, Path},
+            http::{HeaderMap, HeaderName, Request},
+            middleware,
+            routing::{get, post},
+            Router,
+        };
+        use leptos::*;
+        use leptos_axum::{generate_route_list, handle_server_fns_with_context, LeptosRoutes};
+        use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+        use tower_http::trace::{DefaultOnResponse, TraceLayer};
+        use tower_http::LatencyUnit;
+
+        use crate::app::{App, AppProps};
+        use crate::auth::SessionConfig;
+        use crate::authz::server_fn_error_status;
+        use crate::csrf::{csrf_protection, CsrfConfig};
+        use crate::export::{export_items_handler, EXPORT_ITEMS_PATH};
+        use crate::file::file_and_error_handler;
+        use crate::import::{import_items_handler, IMPORT_ITEMS_PATH};
+        use crate::item_events::{item_events_handler, ITEM_EVENTS_PATH};
+        use crate::monitoring::track_metrics;
+        use crate::page_meta::{item_page_handler, open_graph_tags, ITEM_PAGE_PATH};
+        use crate::rate_limit::{rate_limit, RateLimiter};
+        use crate::security_headers::{security_headers, SecurityHeadersConfig};
+        use crate::sitemap::SitemapConfig;
+        use crate::telemetry::{make_request_span, REQUEST_ID_HEADER};
+        use crate::{auth, bulk, csrf, dashboard, feed, health, history, items, rest_api, security_headers, sitemap, trash};
+
+        /// Registers every server function, before the first call to `/api/*fn_name`.
+        pub fn register_server_functions() {
+            items::register_server_functions();
+            trash::register_server_functions();
+            history::register_server_functions();
+            bulk::register_server_functions();
+            auth::register_server_functions();
+            csrf::register_server_functions();
+            dashboard::register_server_functions();
+        }
+
+        /// The public app: server functions, the REST API, health checks, the sitemap, item feeds, the
+        /// SSR routes of `App` and static files from `site_root` or an error page as the fallback.
+        /// Server functions get `session` as a context.
+        pub async fn app_router(
+            leptos_options: LeptosOptions,
+            rate_limiter: Arc<RateLimiter>,
+            security: SecurityHeadersConfig,
+            sitemap: SitemapConfig,
+            session: SessionConfig,
+        ) -> Router {
+            let mut routes = generate_route_list(|cx| view! {cx, <App/> }).await;
+            // Unknown paths go to the fallback, which serves static files or a 404 page. Item pages
+            // have their own handler, which loads the item for the meta tags before rendering.
+            routes.retain(|route| !route.contains('*') && route != ITEM_PAGE_PATH);
+            let security = security.with_live_reload(&leptos_options);
+
+            Router::new()
+                .route(
+                    "/api/*fn_name",
+                    post(move |path: Path<String>, headers: HeaderMap, req: Request<Body>| {
+                        handle_server_fns_with_context(path, headers, move |cx| provide_context(cx, session), req)
+                    })
+                        .layer(middleware::from_fn(server_fn_error_status))
+                        .layer(middleware::from_fn_with_state(rate_limiter, rate_limit)),
+                )
+                .merge(rest_api::router())
+                .merge(health::router())
+                .merge(security_headers::router())
+                .merge(feed::router(sitemap.public_url.clone()))
+                .merge(sitemap::router(sitemap))
+                .route(ITEM_EVENTS_PATH, get(item_events_handler))
+                .route(EXPORT_ITEMS_PATH, get(export_items_handler))
+                .route(IMPORT_ITEMS_PATH, post(import_items_handler))
+                .route(ITEM_PAGE_PATH, get(item_page_handler))
+                .leptos_routes(leptos_options.clone(), routes, |cx| view! {cx, <App/> })
+                .fallback(file_and_error_handler)
+                .layer(middleware::from_fn(open_graph_tags))
+                .layer(middleware::from_fn_with_state(Arc::new(CsrfConfig::default()), csrf_protection))
+                .layer(Extension(Arc::new(leptos_options)))
+                .layer(middleware::from_fn_with_state(Arc::new(security), security_headers))
+                .layer(middleware::from_fn(track_metrics))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(make_request_span)
+                        .on_response(
+                            DefaultOnResponse::new()
+                                .level(tracing::Level::INFO)
+                                .latency_unit(LatencyUnit::Millis),
+                        ),
+                )
+                .layer(PropagateRequestIdLayer::new(HeaderName::from_static(REQUEST_ID_HEADER)))
+                .layer(SetRequestIdLayer::new(
+                    HeaderName::from_static(REQUEST_ID_HEADER),
+                    MakeRequestUuid,
+                ))
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use cfg_if::cfg_if;
+
+pub const SITEMAP_PATH: &str = "/sitemap.xml";
+/// `/sitemaps/{n}.xml`, the sitemaps listed by [SITEMAP_PATH] once it is an index.
+pub const SITEMAP_PAGE_PATH: &str = "/sitemaps/:page";
+pub const ROBOTS_PATH: &str = "/robots.txt";
+/// The most URLs a single sitemap may list according to sitemaps.org.
+pub const MAX_SITEMAP_URLS: usize = 50_000;
+
+/// Pages listed ahead of the items.
+const STATIC_PATHS: [&str; 2] = ["/", "/items"];
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::fmt::Write;
+        use std::sync::Arc;
+
+        use axum::{
+            extract::{Path, State},
+            http::{header, HeaderMap, StatusCode},
+            response::{IntoResponse, Response},
+            routing::get,
+            Router,
+        };
+        use crate::export::EXPORT_ITEMS_PATH;
+        use crate::import::IMPORT_ITEMS_PATH;
+        use crate::item_filter::ItemFilter;
+        use crate::item_repository::{repository, RepositoryError};
+
+        const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+        #[derive(Clone, Debug)]
+        pub struct SitemapConfig {
+            /// Scheme and host the sitemap URLs start with, e.g. `https://example.com`. Taken from
+            /// the request's `Host` header when `None`.
+            pub public_url: Option<String>,
+            pub max_urls: usize,
+            /// Served as is instead of the generated robots.txt.
+            pub robots_txt: Option<String>,
+            /// Asks crawlers to stay away from the whole site, e.g. on staging deployments.
+            pub disallow_all: bool,
+        }
+
+        impl Default for SitemapConfig {
+            fn default() -> Self {
+                SitemapConfig {
+                    public_url: None,
+                    max_urls: MAX_SITEMAP_URLS,
+                    robots_txt: None,
+                    disallow_all: false,
+                }
+            }
+        }
+
+        impl SitemapConfig {
+            /// The defaults, overridden by `PUBLIC_URL`, `ROBOTS_TXT_PATH` (a file replacing the
+            /// generated robots.txt) and `ROBOTS_DISALLOW_ALL=true`.
+            pub fn from_env() -> Self {
+                let robots_txt = std::env::var("ROBOTS_TXT_PATH").ok().map(|path| {
+                    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("couldn't read {path}: {e}"))
+                });
+                SitemapConfig {
+                    public_url: std::env::var("PUBLIC_URL").ok().map(|url| url.trim_end_matches('/').to_string()),
+                    robots_txt,
+                    disallow_all: std::env::var("ROBOTS_DISALLOW_ALL").map_or(false, |v| v == "true" || v == "1"),
+                    ..SitemapConfig::default()
+                }
+            }
+
+            fn base_url(&self, headers: &HeaderMap) -> String {
+                base_url(self.public_url.as_deref(), headers)
+            }
+        }
+
+        /// `public_url`, or the URL of the host the request was sent to.
+        pub(crate) fn base_url(public_url: Option<&str>, headers: &HeaderMap) -> String {
+            match public_url {
+                Some(public_url) => public_url.to_string(),
+                None => {
+                    let host = headers
+                        .get(header::HOST)
+                        .and_then(|host| host.to_str().ok())
+                        .unwrap_or("localhost");
+                    format!("http://{host}")
+                }
+            }
+        }
+
+        pub fn router(config: SitemapConfig) -> Router {
+            Router::new()
+                .route(SITEMAP_PATH, get(sitemap))
+                .route(SITEMAP_PAGE_PATH, get(sitemap_page))
+                .route(ROBOTS_PATH, get(robots_txt))
+                .with_state(Arc::new(config))
+        }
+
+        /// The sitemap of every page, or an index of `/sitemaps/{n}.xml` when there are more than `max_urls`.
+        async fn sitemap(State(config): State<Arc<SitemapConfig>>, headers: HeaderMap) -> Response {
+            let base_url = config.base_url(&headers);
+            let item_count = match count_items().await {
+                Ok(count) => count,
+                Err(e) => return sitemap_error(e),
+            };
+            let pages = (STATIC_PATHS.len() + item_count).div_ceil(config.max_urls.max(1));
+            if pages <= 1 {
+                return sitemap_page_response(&config, &base_url, 1).await;
+            }
+
+            let mut xml = String::from(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+            );
+            for page in 1..=pages {
+                _ = writeln!(xml, "<sitemap><loc>{}/sitemaps/{page}.xml</loc></sitemap>", xml_escape(&base_url));
+            }
+            xml.push_str("</sitemapindex>\n");
+            ([(header::CONTENT_TYPE, XML_CONTENT_TYPE)], xml).into_response()
+        }
+
+        /// `page` is `{n}.xml`, counting from 1.
+        async fn sitemap_page(
+            State(config): State<Arc<SitemapConfig>>,
+            Path(page): Path<String>,
+            headers: HeaderMap,
+        ) -> Response {
+            match page.strip_suffix(".xml").and_then(|n| n.parse().ok()) {
+                Some(page) if page >= 1 => sitemap_page_response(&config, &config.base_url(&headers), page).await,
+                _ => StatusCode::NOT_FOUND.into_response(),
+            }
+        }
+
+        /// The `page`th `max_urls` URLs of the static pages followed by the items, in id order.
+        async fn sitemap_page_response(config: &SitemapConfig, base_url: &str, page: usize) -> Response {
+            let max_urls = config.max_urls.max(1);
+            let start = (page - 1) * max_urls;
+            let end = start + max_urls;
+            let static_paths = STATIC_PATHS.get(start..end.min(STATIC_PATHS.len())).unwrap_or_default();
+            let offset = start.saturating_sub(STATIC_PATHS.len());
+            let limit = end.saturating_sub(start.max(STATIC_PATHS.len()));
+            let items = match repository().last_modified(offset as u32, limit as u32).await {
+                Ok(items) => items,
+                Err(e) => return sitemap_error(e),
+            };
+            if page > 1 && static_paths.is_empty() && items.is_empty() {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+
+            let base_url = xml_escape(base_url);
+            let mut xml = String::from(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+            );
+            for path in static_paths {
+                _ = writeln!(xml, "<url><loc>{base_url}{path}</loc></url>");
+            }
+            for item in items {
+                let lastmod = item
+                    .lastmod
+                    .map(|lastmod| format!("<lastmod>{}</lastmod>", w3c_datetime(&lastmod)))
+                    .unwrap_or_default();
+                _ = writeln!(xml, "<url><loc>{base_url}/items/{}</loc>{lastmod}</url>", item.id);
+            }
+            xml.push_str("</urlset>\n");
+            ([(header::CONTENT_TYPE, XML_CONTENT_TYPE)], xml).into_response()
+        }
+
+        fn sitemap_error(e: RepositoryError) -> Response {
+            tracing::error!(error = %e, "couldn't build the sitemap");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+
+        async fn count_items() -> Result<usize, RepositoryError> {
+            // An empty page still comes with the total.
+            let (_, count) = repository().list(&ItemFilter::default(), 1, 0).await?;
+            Ok(count as usize)
+        }
+
+        /// `YYYY-MM-DD HH:MM:SS` in UTC, SQLite's `CURRENT_TIMESTAMP` format, as a W3C datetime.
+        fn w3c_datetime(sqlite_datetime: &str) -> String {
+            format!("{}+00:00", sqlite_datetime.replacen(' ', "T", 1))
+        }
+
+        pub(crate) fn xml_escape(text: &str) -> String {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+                .replace('\'', "&apos;")
+        }
+
+        /// The configured robots.txt, or one keeping crawlers out of the API, exports and trash
+        /// and pointing them to the sitemap.
+        async fn robots_txt(State(config): State<Arc<SitemapConfig>>, headers: HeaderMap) -> impl IntoResponse {
+            let body = match &config.robots_txt {
+                Some(robots_txt) => robots_txt.clone(),
+                None if config.disallow_all => "User-agent: *\nDisallow: /\n".to_string(),
+                None => format!(
+                    "User-agent: *\nDisallow: /api/\nDisallow: {EXPORT_ITEMS_PATH}\nDisallow: {IMPORT_ITEMS_PATH}\n\
+                     Disallow: /items/trash\n\nSitemap: {}{SITEMAP_PATH}\n",
+                    config.base_url(&headers)
+                ),
+            };
+            ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body)
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use cfg_if::cfg_if;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use axum::{
+            body::Body,
+            extract::MatchedPath,
+            http::Request,
+        };
+        use tower_http::request_id::RequestId;
+        use tracing::{field, Level, Span};
+        use tracing_subscriber::EnvFilter;
+
+        /// Installs the global `tracing` subscriber, which also receives everything logged through
+        /// the `log` crate (including sqlx's statement logs with their `elapsed` time).
+        /// `LOG_FORMAT=json` switches to one JSON object per line, `RUST_LOG` sets the filter.
+        pub fn init_tracing() {
+            let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+            let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+            if std::env::var("LOG_FORMAT").map_or(false, |format| format == "json") {
+                subscriber.json().with_current_span(true).with_span_list(false).init();
+            } else {
+                subscriber.init();
+            }
+        }
+
+        /// Span of one HTTP request, everything logged while handling it is recorded inside.
+        pub fn make_request_span(req: &Request<Body>) -> Span {
+            let route = req.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+            let request_id = req
+                .extensions()
+                .get::<RequestId>()
+                .and_then(|id| id.header_value().to_str().ok())
+                .unwrap_or_default();
+            let span = tracing::span!(
+                Level::INFO,
+                "request",
+                method = %req.method(),
+                uri = %req.uri(),
+                route = route.as_deref().unwrap_or_default(),
+                server_fn = field::Empty,
+                request_id,
+            );
+            if route.as_deref() == Some("/api/*fn_name") {
+                if let Some(server_fn) = req.uri().path().strip_prefix("/api/") {
+                    span.record("server_fn", server_fn);
+                }
+            }
+            span
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use cfg_if::cfg_if;
+use leptos::*;
+use leptos_router::*;
+
+use crate::auth::AuthContext;
+use crate::authz::Permission;
+use crate::csrf::{create_csrf_action, CsrfActionForm, CsrfActionFormProps};
+use crate::items::{MockItem, RemoveItem};
+use crate::pagination::pagination_components::{
+    Pagination, PaginationProps, PaginationStateContext,
+};
+
+pub const DEFAULT_PURGE_AFTER_DAYS: u32 = 30;
+const UNDO_TIMEOUT: Duration = Duration::from_secs(5);
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::auth::Role;
+        use crate::authz::{authorize, authorize_item, AuthError};
+        use crate::item_repository::repository;
+        use crate::item_store;
+
+        pub fn register_server_functions() {
+            _ = GetDeletedItems::register();
+            _ = RestoreItem::register();
+        }
+
+        /// Permanently deletes items that have been in the trash for longer than `after_days`.
+        pub async fn purge_deleted_items(after_days: u32) -> Result<u64, ServerFnError> {
+            repository()
+                .purge_deleted(after_days)
+                .await
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+
+        /// Runs [purge_deleted_items] once an hour for the lifetime of the server.
+        pub fn spawn_purge_task(after_days: u32) {
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+                loop {
+                    interval.tick().await;
+                    match purge_deleted_items(after_days).await {
+                        Ok(0) => {}
+                        Ok(n) => log::info!("Purged {n} deleted items"),
+                        Err(e) => log::error!("Error purging deleted items: {e}"),
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// The trash is for those who can delete items, editors and admins. Editors only see the items
+/// they own, admins all of them.
+#[server(GetDeletedItems, "/api")]
+pub async fn get_deleted_items(
+    cx: Scope,
+    page: u32,
+    page_size: u32,
+) -> Result<(Vec<MockItem>, u32), ServerFnError> {
+    let user = authorize(cx, Permission::CreateItem).await?;
+    let owner_id = user.filter(|user| user.role != Role::Admin).map(|user| user.id);
+    repository()
+        .list_deleted(owner_id, page, page_size)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+#[server(RestoreItem, "/api")]
+pub async fn restore_item(cx: Scope, id: i32) -> Result<(), ServerFnError> {
+    let changed_by = authorize_item(cx, id as i64).await?.map(|user| user.id);
+    item_store::restore(id as i64, changed_by)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or(AuthError::NotFound)?;
+    Ok(())
+}
+
+#[component]
+pub fn TrashView(cx: Scope) -> impl IntoView {
+    view! {cx,
+        <div>
+            <h1>"Trash"</h1>
+            <A href="/items">"Back to Items"</A>
+            <Pagination
+                pagination_link=Box::new(|page, page_size| format!("/items/trash?page={}&page_size={}", page, page_size))
+                page_query_param="page".to_string()
+                page_size_query_param="page_size".to_string()>
+                <DeletedItems/>
+            </Pagination>
+        </div>
+    }
+}
+
+#[component]
+pub fn DeletedItems(cx: Scope) -> impl IntoView {
+    let PaginationStateContext {
+        pagination_state,
+        set_pagination_state,
+    } = use_context(cx).unwrap();
+
+    let restore_item = create_csrf_action::<RestoreItem>(cx);
+    let auth = use_context::<AuthContext>(cx).unwrap();
+
+    let deleted_items = create_resource(
+        cx,
+        move || (pagination_state(), restore_item.version().get()),
+        move |(ps, _)| async move {
+            match get_deleted_items(cx, ps.page() as u32, ps.page_size() as u32).await {
+                Ok((items, total_count)) => {
+                    set_pagination_state.update(|ps| ps.set_element_count(total_count as usize));
+                    items
+                }
+                Err(msg) => {
+                    log::error!("Error reading deleted items: {msg}");
+                    vec![]
+                }
+            }
+        },
+    );
+
+    view! { cx, <div>
+        <Transition fallback=move || view! {cx, <div>"Loading..."</div>}>
+            {move || deleted_items.read().map(|items| view! { cx, <div>
+                <For
+                    each=move || items.clone()
+                    key=|item| item.id
+                    view=move |item: MockItem| {
+                        let (id, owner_id) = (item.id, item.owner_id);
+                        view! { cx,
+                            <div>
+                                <h3>{format!("{} [{}]", item.title, item.id)}</h3>
+                                <p>{item.description}</p>
+                                {move || Permission::ModifyItem { owner_id }
+                                    .allows(auth.current_user().as_ref())
+                                    .then(|| view! { cx,
+                                        <CsrfActionForm action=restore_item>
+                                            <input type="hidden" name="id" value={id}/>
+                                            <input type="submit" value="Restore"/>
+                                        </CsrfActionForm>
+                                    })}
+                            </div>
+                        }
+                    }/>
+            </div>})}
+        </Transition>
+    </div>}
+}
+
+/// Shows a short-lived "Undo" notification after each successful `remove_item` call.
+#[component]
+pub fn UndoRemove(
+    cx: Scope,
+    remove_item: Action<RemoveItem, Result<(), ServerFnError>>,
+    restore_item: Action<RestoreItem, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let (removing_id, set_removing_id) = create_signal(cx, None::<i32>);
+    let (undo_id, set_undo_id) = create_signal(cx, None::<i32>);
+
+    create_effect(cx, move |_| {
+        if let Some(input) = remove_item.input().get() {
+            set_removing_id(Some(input.id));
+        }
+    });
+    create_effect(cx, move |_| {
+        remove_item.version().get();
+        let removed = cx.untrack(move || matches!(remove_item.value().get(), Some(Ok(()))));
+        if let (true, Some(id)) = (removed, cx.untrack(move || removing_id.get())) {
+            set_undo_id(Some(id));
+            set_timeout(
+                move || set_undo_id.update(|undo_id| {
+                    if *undo_id == Some(id) {
+                        *undo_id = None;
+                    }
+                }),
+                UNDO_TIMEOUT,
+            );
+        }
+    });
+    create_effect(cx, move |_| {
+        if restore_item.input().get().is_some() {
+            set_undo_id(None);
+        }
+    });
+
+    move || {
+        undo_id().map(|id| view! { cx,
+            <div class="notification">
+                {format!("Item [{}] deleted ", id)}
+                <CsrfActionForm action=restore_item>
+                    <input type="hidden" name="id" value={id}/>
+                    <input type="submit" value="Undo"/>
+                </CsrfActionForm>
+                <A href="/items/trash">"Trash"</A>
+            </div>
+        })
+    }
+}
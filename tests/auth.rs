@@ -0,0 +1,28 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::auth::Login;
+
+#[tokio::test]
+async fn unknown_usernames_fail_like_wrong_passwords() {
+    let client = TestClient::new();
+    TestClient::registered("login-editor").await;
+
+    let wrong_password = client
+        .call(Login {
+            username: "login-editor".to_string(),
+            password: "not the password".to_string(),
+        })
+        .await
+        .unwrap_err();
+    let unknown_user = client
+        .call(Login {
+            username: "nobody-by-this-name".to_string(),
+            password: "not the password".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(wrong_password.to_string(), unknown_user.to_string());
+}
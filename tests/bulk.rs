@@ -0,0 +1,63 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::TestClient;
+use leptos_playground::bulk::{BulkExportItems, BulkSelection, BulkUpdateItems};
+
+#[tokio::test]
+async fn bulk_update_validates_every_item() {
+    let (client, _) = TestClient::registered("bulk-editor").await;
+    client.add_item("bulk one", "first").await.unwrap();
+    client.add_item("bulk two", "second").await.unwrap();
+    let (items, _) = client.get_items(1, 1000).await.unwrap();
+    let ids: BTreeSet<i64> = items
+        .iter()
+        .filter(|item| item.title.starts_with("bulk "))
+        .map(|item| item.id)
+        .collect();
+    assert_eq!(ids.len(), 2);
+
+    let results = client
+        .call(BulkUpdateItems {
+            selection: BulkSelection::Ids(ids.clone()),
+            title: Some("  ".to_string()),
+            description: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    for result in &results {
+        assert_eq!(result.error.as_deref(), Some("title must not be empty"), "{result:?}");
+        assert!(client.get_item(result.id).await.unwrap().title.starts_with("bulk "));
+    }
+
+    let results = client
+        .call(BulkUpdateItems {
+            selection: BulkSelection::Ids(ids),
+            title: None,
+            description: Some("bulk edited".to_string()),
+        })
+        .await
+        .unwrap();
+    for result in &results {
+        assert_eq!(result.error, None);
+        assert_eq!(client.get_item(result.id).await.unwrap().description, "bulk edited");
+    }
+}
+
+#[tokio::test]
+async fn anonymous_users_can_bulk_export() {
+    let (editor, _) = TestClient::registered("bulk-exporter").await;
+    let item = editor.add_item_and_find("bulk exported", "for anyone").await;
+
+    let exported = TestClient::new()
+        .call(BulkExportItems {
+            selection: BulkSelection::Ids(BTreeSet::from([item.id])),
+        })
+        .await
+        .unwrap();
+    assert_eq!(exported, [item]);
+}
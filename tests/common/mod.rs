@@ -0,0 +1,326 @@
+//! Boots the app from `leptos_playground::server` on an ephemeral port, against a temporary copy
+//! of Items.sqlite with `MIGRATOR` applied, like `main` does at startup, or against the in-memory
+//! repository. The server is started once per test binary and shared by its tests, each
+//! `TestClient` has its own cookies and so its own session.
+//!
+//! The integration tests need the server, run them with
+//! `cargo test --no-default-features --features ssr`.
+#![allow(dead_code)]
+
+pub mod snapshot;
+
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::sync::{mpsc, Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyper::ext::ReasonPhrase;
+use leptos::{get_configuration, Encoding, ServerFn, ServerFnError};
+use leptos_playground::auth::{Login, Register, SessionConfig, User};
+use leptos_playground::csrf::{GetCsrfToken, CSRF_HEADER};
+use leptos_playground::item_repository::memory::InMemoryRepository;
+use leptos_playground::item_repository::set_repository;
+use leptos_playground::item_filter::ItemSort;
+use leptos_playground::items::{
+    set_pool, AddItem, GetItem, GetItems, MockItem, RemoveItem, UpdateItem, MIGRATOR,
+};
+use leptos_playground::rate_limit::{BucketConfig, RateLimitConfig, RateLimiter, Rejection};
+use leptos_playground::security_headers::SecurityHeadersConfig;
+use leptos_playground::server::{app_router, register_server_functions};
+use leptos_playground::sitemap::SitemapConfig;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, ORIGIN};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Executor, SqlitePool};
+
+pub const PASSWORD: &str = "correct horse battery staple";
+
+/// Tests run in parallel and all hit the same server, don't let them throttle each other.
+const UNLIMITED: BucketConfig = BucketConfig {
+    burst: 100_000,
+    per_minute: 100_000,
+};
+
+/// Small enough for the seeded and test items to split the sitemap into an index.
+pub const SITEMAP_MAX_URLS: usize = 5;
+
+static BASE_URL: OnceLock<String> = OnceLock::new();
+static FIXTURE: OnceLock<&'static str> = OnceLock::new();
+static IN_MEMORY: OnceLock<()> = OnceLock::new();
+
+/// Base URL of the shared test server, starting it on first use.
+pub fn base_url() -> &'static str {
+    BASE_URL.get_or_init(|| format!("http://{}", spawn_server()))
+}
+
+/// Runs `sql` on the test database once it is migrated, for the test binaries that need fixed data
+/// rather than the items of Items.sqlite. Has to come before the server is started by [base_url].
+pub fn use_fixture(sql: &'static str) {
+    if FIXTURE.set(sql).is_err() && FIXTURE.get() != Some(&sql) {
+        panic!("the test database already has another fixture");
+    }
+    assert!(BASE_URL.get().is_none(), "the test server started without the fixture");
+}
+
+/// Runs the test server against an empty [InMemoryRepository] instead of Items.sqlite. Has to come
+/// before the server is started by [base_url].
+pub fn use_in_memory_repository() {
+    _ = IN_MEMORY.set(());
+    assert!(BASE_URL.get().is_none(), "the test server started with Items.sqlite");
+}
+
+/// Runs the server on a thread of its own, the runtime of a `#[tokio::test]` only lives as long as its test.
+fn spawn_server() -> SocketAddr {
+    let (addr_tx, addr_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("couldn't start the test runtime");
+        runtime.block_on(async move {
+            if IN_MEMORY.get().is_some() {
+                if set_repository(Arc::new(InMemoryRepository::default())).is_err() {
+                    panic!("the repository was used before the test server started");
+                }
+            } else if set_pool(temp_database().await).is_err() {
+                panic!("the database pool was used before the test server started");
+            }
+            register_server_functions();
+
+            let leptos_options = get_configuration(Some("Cargo.toml")).await.unwrap().leptos_options;
+            let rate_limiter = RateLimiter::new(RateLimitConfig {
+                reads: UNLIMITED,
+                mutations: UNLIMITED,
+                ..RateLimitConfig::default()
+            });
+            let sitemap = SitemapConfig {
+                max_urls: SITEMAP_MAX_URLS,
+                ..SitemapConfig::default()
+            };
+            // The test server is plain HTTP, the client wouldn't send a `Secure` session cookie back.
+            let session = SessionConfig { secure_cookie: false };
+            let security = SecurityHeadersConfig {
+                hsts_max_age: Some(Duration::from_secs(3600)),
+                ..SecurityHeadersConfig::default()
+            };
+            let app = app_router(leptos_options, rate_limiter, security, sitemap, session).await;
+
+            let listener = TcpListener::bind("127.0.0.1:0").expect("couldn't bind an ephemeral port");
+            addr_tx.send(listener.local_addr().unwrap()).unwrap();
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        });
+    });
+    addr_rx.recv().expect("the test server failed to start")
+}
+
+/// A copy of Items.sqlite in the temp dir, migrated and with the fixture of [use_fixture] loaded. The
+/// committed database already has the first migration applied, so the tests start from its items
+/// like the server does.
+async fn temp_database() -> SqlitePool {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let path = std::env::temp_dir().join(format!("leptos_playground-{}-{nanos}.sqlite", std::process::id()));
+    std::fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("Items.sqlite"), &path)
+        .expect("couldn't copy Items.sqlite");
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(SqliteConnectOptions::new().filename(&path))
+        .await
+        .expect("couldn't open the test database");
+
+    MIGRATOR.run(&pool).await.unwrap_or_else(|e| panic!("couldn't migrate the test database: {e}"));
+    if let Some(fixture) = FIXTURE.get() {
+        pool.execute(*fixture).await.unwrap_or_else(|e| panic!("couldn't load the fixture: {e}"));
+    }
+    pool
+}
+
+/// A browser-like client of the test server, keeping the session cookie and CSRF token it got. Its
+/// requests come from the server's own origin unless they set another `Origin`.
+pub struct TestClient {
+    http: reqwest::Client,
+    csrf_token: Option<String>,
+}
+
+impl TestClient {
+    pub fn new() -> Self {
+        let origin = HeaderValue::from_str(base_url()).expect("the base URL is a valid header");
+        TestClient {
+            http: reqwest::Client::builder()
+                .default_headers(HeaderMap::from_iter([(ORIGIN, origin)]))
+                .cookie_store(true)
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap(),
+            csrf_token: None,
+        }
+    }
+
+    /// A client logged in as a new editor called `username`.
+    pub async fn registered(username: &str) -> (Self, User) {
+        let mut client = TestClient::new();
+        let user = client.register(username).await;
+        (client, user)
+    }
+
+    pub async fn get(&self, path: &str) -> reqwest::Response {
+        self.http
+            .get(format!("{}{path}", base_url()))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    /// A request to `path` carrying the client's cookies, for the routes that aren't server functions.
+    pub fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http.request(method, format!("{}{path}", base_url()))
+    }
+
+    /// The CSRF token of the client's session, for [TestClient::request]s that need one.
+    pub fn csrf_token(&self) -> &str {
+        self.csrf_token.as_deref().unwrap_or_default()
+    }
+
+    /// The server-rendered HTML of `path`, once every resource on it has resolved.
+    pub async fn page(&self, path: &str) -> String {
+        let res = self.get(path).await;
+        assert_eq!(res.status(), StatusCode::OK, "GET {path}");
+        res.text().await.unwrap()
+    }
+
+    /// Calls a server function the way `csrf::create_csrf_action` does, with the session's CSRF
+    /// token in the `CSRF_HEADER`.
+    pub async fn call<F>(&self, args: F) -> Result<F::Output, ServerFnError>
+    where
+        F: ServerFn,
+        F::Output: DeserializeOwned,
+    {
+        let url = format!("{}{}/{}", base_url(), F::prefix(), F::url());
+        let (content_type, body) = match F::encoding() {
+            Encoding::Url => {
+                let body = serde_urlencoded::to_string(&args)
+                    .map_err(|e| ServerFnError::Serialization(e.to_string()))?;
+                ("application/x-www-form-urlencoded", body.into_bytes())
+            }
+            Encoding::Cbor => {
+                let mut body = vec![];
+                ciborium::ser::into_writer(&args, &mut body)
+                    .map_err(|e| ServerFnError::Serialization(e.to_string()))?;
+                ("application/cbor", body)
+            }
+        };
+
+        let res = self
+            .http
+            .post(url)
+            .header(CONTENT_TYPE, content_type)
+            .header(ACCEPT, content_type)
+            .header(CSRF_HEADER, self.csrf_token())
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ServerFnError::Request(e.to_string()))?;
+        // Like `csrf::create_csrf_action`, only the status text of a 5xx or a rejection is kept: the
+        // reason phrase the server sent, not the canonical one reqwest reports.
+        let status = res.status();
+        if status.is_server_error() || Rejection::has_status(status.as_u16()) {
+            let status_text = res
+                .extensions()
+                .get::<ReasonPhrase>()
+                .map(|reason| String::from_utf8_lossy(reason.as_bytes()).into_owned())
+                .or_else(|| status.canonical_reason().map(str::to_string))
+                .unwrap_or_default();
+            return Err(ServerFnError::ServerError(status_text));
+        }
+        let body = res.bytes().await.map_err(|e| ServerFnError::Deserialization(e.to_string()))?;
+        match F::encoding() {
+            Encoding::Url => {
+                serde_json::from_slice(&body).map_err(|e| ServerFnError::Deserialization(e.to_string()))
+            }
+            Encoding::Cbor => {
+                ciborium::de::from_reader(&body[..]).map_err(|e| ServerFnError::Deserialization(e.to_string()))
+            }
+        }
+    }
+
+    /// Registers and logs in a new user, then picks up the CSRF token of the new session.
+    pub async fn register(&mut self, username: &str) -> User {
+        let user = self
+            .call(Register {
+                username: username.to_string(),
+                password: PASSWORD.to_string(),
+                password_confirmation: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap_or_else(|e| panic!("couldn't register {username}: {e}"));
+        self.refresh_csrf_token().await;
+        user
+    }
+
+    pub async fn login(&mut self, username: &str) -> User {
+        let user = self
+            .call(Login {
+                username: username.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap_or_else(|e| panic!("couldn't log in as {username}: {e}"));
+        self.refresh_csrf_token().await;
+        user
+    }
+
+    async fn refresh_csrf_token(&mut self) {
+        self.csrf_token = self.call(GetCsrfToken {}).await.unwrap();
+    }
+
+    pub async fn get_items(&self, page: u32, page_size: u32) -> Result<(Vec<MockItem>, u32), ServerFnError> {
+        self.call(GetItems {
+            page,
+            page_size,
+            q: None,
+            sort: ItemSort::default(),
+        })
+        .await
+    }
+
+    pub async fn get_item(&self, id: i64) -> Result<MockItem, ServerFnError> {
+        self.call(GetItem { id: id as i32 }).await
+    }
+
+    /// The listed item called `title`, e.g. one the test has just added or imported.
+    pub async fn find_item(&self, title: &str) -> MockItem {
+        let (items, _) = self.get_items(1, 1000).await.unwrap();
+        items
+            .into_iter()
+            .find(|item| item.title == title)
+            .unwrap_or_else(|| panic!("{title:?} is not listed"))
+    }
+
+    /// Adds an item and returns it as it is listed.
+    pub async fn add_item_and_find(&self, title: &str, description: &str) -> MockItem {
+        self.add_item(title, description).await.unwrap();
+        self.find_item(title).await
+    }
+
+    pub async fn add_item(&self, title: &str, description: &str) -> Result<(), ServerFnError> {
+        self.call(AddItem {
+            title: title.to_string(),
+            description: description.to_string(),
+        })
+        .await
+    }
+
+    pub async fn update_item(&self, id: i64, title: &str, description: &str) -> Result<(), ServerFnError> {
+        self.call(UpdateItem {
+            id,
+            title: title.to_string(),
+            description: description.to_string(),
+        })
+        .await
+    }
+
+    pub async fn remove_item(&self, id: i64) -> Result<(), ServerFnError> {
+        self.call(RemoveItem { id: id as i32 }).await
+    }
+}
@@ -0,0 +1,100 @@
+//! Renders components to HTML on the server and compares the markup against the snapshots in
+//! `tests/snapshots`. Run with `UPDATE_SNAPSHOTS=1` to record new snapshots or accept changed markup,
+//! a missing snapshot fails the test otherwise.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use leptos::*;
+use leptos_router::{RouterIntegrationContext, ServerIntegration};
+use regex::Regex;
+use tokio::task::LocalSet;
+
+/// Renders `view` the way the server renders the shell of a page at `path`: inside a router, with
+/// every resource still pending. Resources are spawned on a `LocalSet` but never polled.
+pub async fn render<F, N>(path: &str, view: F) -> String
+where
+    F: FnOnce(Scope) -> N + 'static,
+    N: IntoView,
+{
+    let path = format!("http://leptos.dev{path}");
+    LocalSet::new()
+        .run_until(async move {
+            render_to_string(move |cx| {
+                provide_context(cx, RouterIntegrationContext::new(ServerIntegration { path }));
+                view(cx)
+            })
+        })
+        .await
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// Makes rendered HTML deterministic: hydration keys, CSP nonces, dates and timestamps are replaced by placeholders,
+/// and the Suspense fragments and resource data streamed after the shell, which arrive in the order
+/// their resources resolve, are sorted.
+pub fn normalize_html(html: &str) -> String {
+    static HYDRATION_KEY: OnceLock<Regex> = OnceLock::new();
+    static NONCE: OnceLock<Regex> = OnceLock::new();
+    static TIMESTAMP: OnceLock<Regex> = OnceLock::new();
+    static STREAMED_CHUNK: OnceLock<Regex> = OnceLock::new();
+
+    let html = regex(&HYDRATION_KEY, r"\b_[0-9]+(?:-[0-9]+)*[cf]?\b").replace_all(html, "_hk");
+    let html = regex(&NONCE, r#"nonce="[0-9a-f]+""#).replace_all(&html, r#"nonce="<nonce>""#);
+    let html = regex(&TIMESTAMP, r"\d{4}-\d{2}-\d{2}(?:[ T]\d{2}:\d{2}:\d{2}(?:\.\d+)?Z?)?")
+        .replace_all(&html, "<timestamp>");
+
+    let mut shell = String::new();
+    let mut chunks = vec![];
+    let mut last = 0;
+    let chunk = regex(
+        &STREAMED_CHUNK,
+        r"(?s)<template id=.*?</template>\s*<script>.*?</script>|<script>\s*if\(__LEPTOS_RESOURCE_RESOLVERS.*?</script>",
+    );
+    for m in chunk.find_iter(&html) {
+        shell.push_str(&html[last..m.start()]);
+        chunks.push(m.as_str().trim().to_string());
+        last = m.end();
+    }
+    shell.push_str(&html[last..]);
+    chunks.sort();
+
+    let mut normalized: Vec<&str> = shell.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    normalized.extend(chunks.iter().flat_map(|chunk| chunk.lines().map(str::trim)));
+    normalized.join("\n") + "\n"
+}
+
+/// Compares the normalized `html` against `tests/snapshots/{name}.html`.
+pub fn assert_snapshot(name: &str, html: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("tests/snapshots/{name}.html"));
+    let actual = normalize_html(html);
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => {}
+        Err(e) if !update => panic!(
+            "snapshot {name} couldn't be read ({e}), run with UPDATE_SNAPSHOTS=1 to record it\n--- actual ---\n{actual}"
+        ),
+        Ok(expected) if !update => {
+            let diff: Vec<String> = expected
+                .lines()
+                .zip(actual.lines())
+                .enumerate()
+                .filter(|(_, (expected, actual))| expected != actual)
+                .take(10)
+                .map(|(i, (expected, actual))| format!("line {}:\n- {expected}\n+ {actual}", i + 1))
+                .collect();
+            panic!(
+                "snapshot {name} changed, rerun with UPDATE_SNAPSHOTS=1 if that's intended\n{}\n--- actual ---\n{actual}",
+                diff.join("\n")
+            );
+        }
+        _ => {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            eprintln!("recorded snapshot {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos::ServerFn;
+use leptos_playground::csrf::{CSRF_FIELD, CSRF_HEADER};
+use leptos_playground::items::AddItem;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+
+#[tokio::test]
+async fn cross_origin_writes_are_rejected_on_every_route() {
+    let (client, _) = TestClient::registered("csrf-cross-origin").await;
+
+    let res = client
+        .request(Method::POST, "/api/v1/items")
+        .header("origin", "https://evil.example")
+        .json(&json!({ "title": "cross-origin", "description": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .request(Method::POST, "/import/items?format=csv")
+        .header("origin", "https://evil.example")
+        .header(CSRF_HEADER, client.csrf_token())
+        .body("title,description\ncross-origin,import\n")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn form_like_imports_need_the_session_token() {
+    let (client, _) = TestClient::registered("csrf-importer").await;
+    let csv = "title,description\nimported without a token,row\n";
+
+    let res = client
+        .request(Method::POST, "/import/items?format=csv")
+        .header("content-type", "text/plain")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .request(Method::POST, "/import/items?format=csv")
+        .header("content-type", "text/plain")
+        .header(CSRF_HEADER, "not the token")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .request(Method::POST, "/import/items?format=csv&dry_run=true")
+        .header("content-type", "text/plain")
+        .header(CSRF_HEADER, client.csrf_token())
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn writes_without_an_origin_are_rejected() {
+    let res = reqwest::Client::new()
+        .post(format!("{}/api/v1/items", common::base_url()))
+        .json(&json!({ "title": "no origin", "description": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn writes_need_the_session_token_whatever_their_body() {
+    let (client, _) = TestClient::registered("csrf-json").await;
+
+    let res = client
+        .request(Method::POST, "/api/v1/items")
+        .json(&json!({ "title": "json without a token", "description": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn posted_forms_carry_the_token_in_a_field() {
+    let (client, _) = TestClient::registered("csrf-form").await;
+    let form = |csrf_token: &str| {
+        serde_urlencoded::to_string([
+            ("title", "posted form"),
+            ("description", "with the token field"),
+            (CSRF_FIELD, csrf_token),
+        ])
+        .unwrap()
+    };
+    let post = |body: String| {
+        client
+            .request(Method::POST, &format!("{}/{}", AddItem::prefix(), AddItem::url()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("accept", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+    };
+
+    let res = post(form("not the token")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = post(form(client.csrf_token())).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let (items, _) = client.get_items(1, 1000).await.unwrap();
+    assert!(items.iter().any(|item| item.title == "posted form"));
+}
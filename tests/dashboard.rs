@@ -0,0 +1,27 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::dashboard::{GetItemStats, STATS_DAYS};
+
+#[tokio::test]
+async fn item_stats_count_todays_additions_and_edits() {
+    let (client, _) = TestClient::registered("stats-editor").await;
+    let item = client.add_item_and_find("stats item", "counted").await;
+    client.update_item(item.id, "stats item edited", "counted").await.unwrap();
+
+    let stats = TestClient::new().call(GetItemStats {}).await.unwrap();
+    assert!(stats.total >= 6, "the items of Items.sqlite count too: {stats:?}");
+    assert_eq!(stats.added_per_day.len(), STATS_DAYS as usize);
+    assert!(stats.added_per_day.last().unwrap().count >= 1, "{stats:?}");
+    assert!(stats.recently_edited.iter().any(|edited| edited.id == item.id), "{stats:?}");
+}
+
+#[tokio::test]
+async fn home_page_renders_the_chart_on_the_server() {
+    let html = TestClient::new().page("/").await;
+    assert!(html.contains("<title>Home | Leptos Playground</title>"), "{html}");
+    assert!(html.contains("class=\"items-per-day\""), "{html}");
+    assert_eq!(html.matches("<rect").count(), STATS_DAYS as usize, "{html}");
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn csv_export_header_matches_the_rows() {
+    let (client, user) = TestClient::registered("export-editor").await;
+    client.add_item("exported, with a comma", "csv row").await.unwrap();
+
+    let res = client.get("/export/items?format=csv&q=exported").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("text/csv"));
+    let csv = res.text().await.unwrap();
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    assert_eq!(reader.headers().unwrap(), vec!["id", "title", "description", "owner_id"]);
+    let rows = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(rows.len(), 1, "{csv}");
+    assert_eq!(&rows[0][1], "exported, with a comma");
+    assert_eq!(&rows[0][3], user.id.to_string());
+}
+
+#[tokio::test]
+async fn empty_csv_export_still_has_the_header() {
+    let (client, _) = TestClient::registered("empty-export-editor").await;
+    let csv = client.page("/export/items?format=csv&q=nothing+matches+this").await;
+    assert_eq!(csv, "id,title,description,owner_id\n");
+}
+
+#[tokio::test]
+async fn anonymous_users_can_export() {
+    let res = TestClient::new().get("/export/items?format=csv").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let csv = res.text().await.unwrap();
+    assert!(csv.starts_with("id,title,description,owner_id\n"), "{csv}");
+}
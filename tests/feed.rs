@@ -0,0 +1,82 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn atom_feed_lists_new_items_first() {
+    let (client, _) = TestClient::registered("feed-editor").await;
+    client.add_item("feed & first", "older").await.unwrap();
+    client.add_item("feed second", "newer").await.unwrap();
+    let (items, _) = client.get_items(1, 1000).await.unwrap();
+    let second = items.iter().find(|item| item.title == "feed second").unwrap();
+
+    let res = TestClient::new().get("/items/feed.atom?q=feed").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("application/atom+xml"));
+    let xml = res.text().await.unwrap();
+    assert!(xml.contains(&format!("<link href=\"{}/items/{}\"/>", common::base_url(), second.id)), "{xml}");
+    let second_at = xml.find("<title>feed second</title>").expect("newer item listed");
+    let first_at = xml.find("<title>feed &amp; first</title>").expect("older item listed, escaped");
+    assert!(second_at < first_at, "{xml}");
+    assert!(!xml.contains("<title>foo4</title>"), "the filter applies: {xml}");
+}
+
+#[tokio::test]
+async fn rss_feed_honors_the_limit() {
+    let res = TestClient::new().get("/items/feed.rss?limit=2").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("application/rss+xml"));
+    let xml = res.text().await.unwrap();
+    assert!(xml.contains("<rss version=\"2.0\">"), "{xml}");
+    assert_eq!(xml.matches("<item>").count(), 2, "{xml}");
+}
+
+#[tokio::test]
+async fn feeds_answer_conditional_requests() {
+    let client = TestClient::new();
+    let res = client.get("/items/feed.atom?q=foo4").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let etag = res.headers()["etag"].to_str().unwrap().to_string();
+
+    let res = reqwest::Client::new()
+        .get(format!("{}/items/feed.atom?q=foo4", common::base_url()))
+        .header("if-none-match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()["etag"], etag.as_str());
+
+    let res = reqwest::Client::new()
+        .get(format!("{}/items/feed.atom?q=foo4", common::base_url()))
+        .header("if-none-match", "\"stale\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn feeds_are_modified_by_deletions() {
+    let (client, _) = TestClient::registered("feed-deleter").await;
+    client.add_item("feed-deletion kept", "").await.unwrap();
+    let deleted = client.add_item_and_find("feed-deletion deleted", "").await;
+    let res = TestClient::new().get("/items/feed.atom?q=feed-deletion").await;
+    let last_modified = res.headers()["last-modified"].to_str().unwrap().to_string();
+
+    // `Last-Modified` has a resolution of seconds.
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    client.remove_item(deleted.id).await.unwrap();
+    let res = reqwest::Client::new()
+        .get(format!("{}/items/feed.atom?q=feed-deletion", common::base_url()))
+        .header("if-modified-since", &last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_ne!(res.headers()["last-modified"], last_modified.as_str());
+    assert!(!res.text().await.unwrap().contains("feed-deletion deleted"));
+}
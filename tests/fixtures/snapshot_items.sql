@@ -0,0 +1,7 @@
+-- The items the `app_routes` snapshots render: two pages of two with `page_size=2` and one in the trash.
+DELETE FROM items;
+INSERT INTO items (id, title, description, deleted_at)
+VALUES (1, 'First snapshot item', 'Shown on its own page', NULL),
+       (2, 'Second snapshot item', 'Listed next to the first one', NULL),
+       (3, 'Third snapshot item', 'Alone on the second page', NULL),
+       (4, 'Trashed snapshot item', 'Only listed in the trash', '2023-03-01 12:00:00');
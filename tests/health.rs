@@ -0,0 +1,16 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+#[tokio::test]
+async fn ready_once_every_embedded_migration_is_applied() {
+    let res = TestClient::new().get("/readyz").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let readiness: Value = res.json().await.unwrap();
+    assert_eq!(readiness["missing_migrations"], Value::Array(vec![]), "{readiness}");
+    assert!(readiness.get("repository_error").is_none(), "{readiness}");
+}
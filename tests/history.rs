@@ -0,0 +1,42 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::authz::AuthError;
+use leptos_playground::history::{GetItemRevisions, RevertItem};
+
+#[tokio::test]
+async fn revisions_record_who_changed_the_item() {
+    let (client, _) = TestClient::registered("history-editor").await;
+    let item = client.add_item_and_find("history item", "first").await;
+    client.update_item(item.id, "history item", "second").await.unwrap();
+
+    let revisions = client.call(GetItemRevisions { item_id: item.id }).await.unwrap();
+    let operations: Vec<_> = revisions
+        .iter()
+        .map(|revision| (revision.operation.as_str(), revision.changed_by.as_deref()))
+        .collect();
+    assert_eq!(
+        operations,
+        [("update", Some("history-editor")), ("insert", Some("history-editor"))]
+    );
+
+    let html = client.page(&format!("/items/{}", item.id)).await;
+    assert!(html.contains("by history-editor"), "{html}");
+}
+
+#[tokio::test]
+async fn trashed_items_cannot_be_reverted() {
+    let (client, _) = TestClient::registered("history-reverter").await;
+    let item = client.add_item_and_find("reverted item", "first").await;
+    client.update_item(item.id, "reverted item", "second").await.unwrap();
+    let revisions = client.call(GetItemRevisions { item_id: item.id }).await.unwrap();
+    let first = revisions.iter().find(|revision| revision.operation == "insert").unwrap();
+    client.remove_item(item.id).await.unwrap();
+
+    let err = client.call(RevertItem { revision_id: first.id }).await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::NotFound), "{err}");
+    let revisions = client.call(GetItemRevisions { item_id: item.id }).await.unwrap();
+    assert_eq!(revisions[0].operation, "delete");
+}
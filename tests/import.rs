@@ -0,0 +1,46 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::csrf::CSRF_HEADER;
+use leptos_playground::history::GetItemRevisions;
+use leptos_playground::import::ImportReport;
+use reqwest::{Method, StatusCode};
+
+async fn import(client: &TestClient, query: &str, csv: &'static str) -> ImportReport {
+    let res = client
+        .request(Method::POST, &format!("/import/items?{query}"))
+        .header(CSRF_HEADER, client.csrf_token())
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    res.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn all_or_nothing_reports_valid_rows_as_not_imported() {
+    let (client, _) = TestClient::registered("import-strict-editor").await;
+    let report = import(
+        &client,
+        "format=csv&mode=all_or_nothing",
+        "title,description\nstrict one,valid\n,no title\n",
+    )
+    .await;
+    assert_eq!((report.inserted, report.skipped, report.failed, report.not_imported), (0, 0, 1, 1));
+    assert_eq!(report.errors[0].row, 2);
+}
+
+#[tokio::test]
+async fn imported_items_get_a_history() {
+    let (client, _) = TestClient::registered("import-editor").await;
+    let report = import(&client, "format=csv&mode=skip_invalid", "title,description\nimported item,from csv\n").await;
+    assert_eq!(report.inserted, 1, "{report:?}");
+
+    let item = client.find_item("imported item").await;
+    let revisions = client.call(GetItemRevisions { item_id: item.id }).await.unwrap();
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].changed_by.as_deref(), Some("import-editor"));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::{use_in_memory_repository, TestClient};
+use leptos_playground::history::GetItemRevisions;
+use leptos_playground::trash::{GetDeletedItems, RestoreItem};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn items_live_through_edits_the_trash_and_their_history() {
+    use_in_memory_repository();
+    let (client, _) = TestClient::registered("memory-editor").await;
+    client.add_item("memory item", "first").await.unwrap();
+    let (items, total) = client.get_items(1, 1000).await.unwrap();
+    assert_eq!(total as usize, items.len());
+    let item = items.into_iter().find(|item| item.title == "memory item").unwrap();
+
+    client.update_item(item.id, "memory item", "second").await.unwrap();
+    client.remove_item(item.id).await.unwrap();
+    let (deleted, _) = client.call(GetDeletedItems { page: 1, page_size: 1000 }).await.unwrap();
+    assert!(deleted.iter().any(|deleted| deleted.id == item.id));
+    client.call(RestoreItem { id: item.id as i32 }).await.unwrap();
+    assert_eq!(client.get_item(item.id).await.unwrap().description, "second");
+
+    let revisions = client.call(GetItemRevisions { item_id: item.id }).await.unwrap();
+    let operations: Vec<_> = revisions
+        .iter()
+        .map(|revision| (revision.operation.as_str(), revision.changed_by.as_deref()))
+        .collect();
+    assert_eq!(
+        operations,
+        [
+            ("restore", Some("memory-editor")),
+            ("delete", Some("memory-editor")),
+            ("update", Some("memory-editor")),
+            ("insert", Some("memory-editor")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn ready_without_a_database() {
+    use_in_memory_repository();
+    let res = TestClient::new().get("/readyz").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
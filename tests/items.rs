@@ -0,0 +1,102 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos::ServerFn;
+use leptos_playground::auth::Role;
+use leptos_playground::authz::AuthError;
+use leptos_playground::csrf::CSRF_HEADER;
+use leptos_playground::items::{AddItem, GetItem};
+use leptos_playground::rate_limit::{describe_error, Rejection};
+use reqwest::{Method, StatusCode};
+
+#[tokio::test]
+async fn editor_can_add_update_and_remove_own_item() {
+    let (client, user) = TestClient::registered("crud-editor").await;
+    assert_eq!(user.role, Role::Editor);
+
+    client.add_item("crud title", "crud description").await.unwrap();
+    let (items, total) = client.get_items(1, 1000).await.unwrap();
+    let item = items
+        .into_iter()
+        .find(|item| item.title == "crud title")
+        .expect("added item is listed");
+    assert!(total >= 1);
+    assert_eq!(item.description, "crud description");
+    assert_eq!(item.owner_id, Some(user.id));
+
+    client.update_item(item.id, "renamed", "changed").await.unwrap();
+    let updated = client.get_item(item.id).await.unwrap();
+    assert_eq!((updated.title.as_str(), updated.description.as_str()), ("renamed", "changed"));
+
+    client.remove_item(item.id).await.unwrap();
+    assert!(client.get_item(item.id).await.is_err());
+}
+
+#[tokio::test]
+async fn anonymous_users_can_read_but_not_add() {
+    let client = TestClient::new();
+    let (items, _) = client.get_items(1, 4).await.unwrap();
+    assert!(!items.is_empty(), "Items.sqlite comes with some items");
+
+    let err = client.add_item("anonymous", "not allowed").await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::LoginRequired), "{err}");
+}
+
+#[tokio::test]
+async fn editors_cannot_change_items_of_others() {
+    let (owner, _) = TestClient::registered("owner-editor").await;
+    let item = owner.add_item_and_find("owned item", "by owner-editor").await;
+
+    let (other, _) = TestClient::registered("other-editor").await;
+    let err = other.update_item(item.id, "hijacked", "nope").await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::Forbidden), "{err}");
+    let err = other.remove_item(item.id).await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::Forbidden), "{err}");
+    assert_eq!(owner.get_item(item.id).await.unwrap().title, "owned item");
+}
+
+#[tokio::test]
+async fn rejected_calls_are_told_apart() {
+    let err = TestClient::new().add_item("anonymous", "not allowed").await.unwrap_err();
+    assert_eq!(describe_error(&err), "please log in first");
+
+    let (client, _) = TestClient::registered("oversized-editor").await;
+    let err = client.add_item("oversized", &"x".repeat(100 * 1024)).await.unwrap_err();
+    assert_eq!(Rejection::from_server_fn_error(&err), Some(Rejection::PayloadTooLarge), "{err}");
+    assert_eq!(describe_error(&err), "too much data in one request");
+
+    let res = client
+        .request(Method::POST, &format!("{}/{}", AddItem::prefix(), AddItem::url()))
+        .header(CSRF_HEADER, client.csrf_token())
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body("x".repeat(100 * 1024))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn invalid_items_are_rejected() {
+    let (client, _) = TestClient::registered("validating-editor").await;
+    let err = client.add_item("", "no title").await.unwrap_err();
+    assert!(err.to_string().contains("title must not be empty"), "{err}");
+}
+
+#[tokio::test]
+async fn missing_items_are_not_found() {
+    let err = TestClient::new().call(GetItem { id: i32::MAX }).await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::NotFound), "{err}");
+}
+
+#[tokio::test]
+async fn items_page_renders_pagination() {
+    let client = TestClient::new();
+    let html = client.page("/items?page=1&page_size=4").await;
+    assert!(html.contains("Items</h1>"), "{html}");
+    // Items.sqlite comes with seven items, so with four per page there is at least a second page.
+    assert!(html.contains("page=2"), "{html}");
+    assert!(html.contains("foo4"), "{html}");
+}
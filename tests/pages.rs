@@ -0,0 +1,119 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::security_headers::ScriptNonces;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn unknown_paths_render_the_error_page_with_404() {
+    let res = TestClient::new().get("/no/such/page").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let html = res.text().await.unwrap();
+    assert!(html.contains("page not found"), "{html}");
+}
+
+#[tokio::test]
+async fn pages_have_their_own_title() {
+    let html = TestClient::new().page("/items").await;
+    assert!(html.contains("<title>Items | Leptos Playground</title>"), "{html}");
+    assert_eq!(html.matches(r#"name="description""#).count(), 1, "{html}");
+}
+
+#[tokio::test]
+async fn item_pages_carry_the_item_in_their_meta_tags() {
+    let (client, _) = TestClient::registered("meta-editor").await;
+    let item = client.add_item_and_find("preview title", "preview description").await;
+
+    // Crawlers are anonymous and don't run the app's wasm.
+    let html = TestClient::new().page(&format!("/items/{}", item.id)).await;
+    let head = &html[..html.find("</head>").expect("has a head")];
+    assert!(head.contains("<title>preview title | Leptos Playground</title>"), "{head}");
+    for name in ["description", "twitter:description"] {
+        assert!(
+            head.contains(&format!(r#"name="{name}" content="preview description""#)),
+            "{name} missing in {head}"
+        );
+    }
+    assert!(head.contains(r#"property="og:description" content="preview description""#), "{head}");
+    assert!(head.contains(r#"property="og:title" content="preview title""#), "{head}");
+    assert!(head.contains(r#"property="og:type" content="article""#), "{head}");
+    assert!(!head.contains(r#"name="og:"#), "{head}");
+}
+
+
+#[tokio::test]
+async fn pages_carry_security_headers_and_script_nonces() {
+    let res = TestClient::new().get("/items").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let headers = res.headers();
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert!(headers.contains_key("strict-transport-security"));
+    assert!(headers.contains_key("referrer-policy"));
+    let csp = headers["content-security-policy"].to_str().unwrap().to_string();
+    assert!(csp.contains("frame-ancestors 'none'"), "{csp}");
+
+    let nonce = csp
+        .split(['\'', ' '])
+        .find_map(|part| part.strip_prefix("nonce-"))
+        .expect("script-src has a nonce")
+        .to_string();
+    let html = res.text().await.unwrap();
+    let scripts = html.matches("<script").count();
+    assert!(scripts > 0);
+    assert_eq!(html.matches(&format!("<script nonce=\"{nonce}\"")).count(), scripts, "{html}");
+}
+
+#[tokio::test]
+async fn scripts_in_item_data_get_no_nonce() {
+    let (client, _) = TestClient::registered("nonce-editor").await;
+    client.add_item("</title><script>injected()</script>", "script in the title").await.unwrap();
+    let (items, _) = client.get_items(1, 1000).await.unwrap();
+    let item = items.into_iter().find(|item| item.description == "script in the title").unwrap();
+
+    let res = TestClient::new().get(&format!("/items/{}", item.id)).await;
+    let csp = res.headers()["content-security-policy"].to_str().unwrap().to_string();
+    let nonce = csp
+        .split(['\'', ' '])
+        .find_map(|part| part.strip_prefix("nonce-"))
+        .expect("script-src has a nonce")
+        .to_string();
+    let html = res.text().await.unwrap();
+    assert!(html.contains(&format!("<script nonce=\"{nonce}\"")), "{html}");
+    assert!(!html.contains(&format!("<script nonce=\"{nonce}\">injected()")), "{html}");
+    assert!(html.contains("<title>&lt;/title&gt;&lt;script&gt;injected()"), "{html}");
+}
+
+#[test]
+fn script_nonces_are_added_across_chunks() {
+    let html = concat!(
+        r#"<head><title>a <script> title</title><script type="module">import('<script>');</script>"#,
+        r#"<!-- <script> --></head><body><p title="x">1 < 2</p><script src="/x.js"></script>"#,
+        r#"<textarea><script></textarea><script>var s = "</scr" + "ipt>";</script></body>"#,
+    );
+    let expected = concat!(
+        r#"<head><title>a <script> title</title><script nonce="n" type="module">import('<script>');</script>"#,
+        r#"<!-- <script> --></head><body><p title="x">1 < 2</p><script src="/x.js"></script>"#,
+        r#"<textarea><script></textarea><script nonce="n">var s = "</scr" + "ipt>";</script></body>"#,
+    );
+    for split in 0..=html.len() {
+        let mut nonces = ScriptNonces::new("n");
+        let mut out = nonces.push(&html.as_bytes()[..split]);
+        out.extend(nonces.push(&html.as_bytes()[split..]));
+        out.extend(nonces.finish());
+        assert_eq!(String::from_utf8(out).unwrap(), expected, "split at {split}");
+    }
+}
+
+#[tokio::test]
+async fn csp_reports_are_accepted() {
+    let res = reqwest::Client::new()
+        .post(format!("{}/csp-report", common::base_url()))
+        .header("content-type", "application/csp-report")
+        .body(r#"{"csp-report":{"violated-directive":"script-src"}}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
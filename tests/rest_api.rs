@@ -0,0 +1,140 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::csrf::CSRF_HEADER;
+use leptos_playground::items::MockItem;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+async fn error_message(res: reqwest::Response) -> String {
+    let body: Value = res.json().await.unwrap();
+    body["error"]["message"].as_str().unwrap().to_string()
+}
+
+/// An item added by a new editor called `username`.
+async fn owned_item(username: &str) -> MockItem {
+    let (owner, _) = TestClient::registered(username).await;
+    let res = owner
+        .request(Method::POST, "/api/v1/items")
+        .header(CSRF_HEADER, owner.csrf_token())
+        .json(&json!({ "title": format!("{username} item"), "description": "owned" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    res.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn rest_api_items_are_owned_by_their_creator() {
+    let (client, user) = TestClient::registered("rest-creator").await;
+    let res = client
+        .request(Method::POST, "/api/v1/items")
+        .header(CSRF_HEADER, client.csrf_token())
+        .json(&json!({ "title": "rest item", "description": "created" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let item: MockItem = res.json().await.unwrap();
+    assert_eq!(item.owner_id, Some(user.id));
+
+    let res = client
+        .request(Method::PATCH, &format!("/api/v1/items/{}", item.id))
+        .header(CSRF_HEADER, client.csrf_token())
+        .json(&json!({ "title": "rest item renamed" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = client
+        .request(Method::DELETE, &format!("/api/v1/items/{}", item.id))
+        .header(CSRF_HEADER, client.csrf_token())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn rest_api_mutations_require_login() {
+    let item = owned_item("rest-anon-owner").await;
+    let anonymous = TestClient::new();
+
+    let res = anonymous
+        .request(Method::POST, "/api/v1/items")
+        .json(&json!({ "title": "anonymous", "description": "not allowed" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(error_message(res).await, "login required");
+
+    let res = anonymous
+        .request(Method::PATCH, &format!("/api/v1/items/{}", item.id))
+        .json(&json!({ "title": "hijacked" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = anonymous
+        .request(Method::DELETE, &format!("/api/v1/items/{}", item.id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn rest_api_editors_cannot_change_items_of_others() {
+    let item = owned_item("rest-owner").await;
+    let (other, _) = TestClient::registered("rest-other").await;
+
+    let res = other
+        .request(Method::PATCH, &format!("/api/v1/items/{}", item.id))
+        .header(CSRF_HEADER, other.csrf_token())
+        .json(&json!({ "title": "hijacked" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    assert_eq!(error_message(res).await, "forbidden");
+    let res = other
+        .request(Method::DELETE, &format!("/api/v1/items/{}", item.id))
+        .header(CSRF_HEADER, other.csrf_token())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = other.get(&format!("/api/v1/items/{}", item.id)).await;
+    let unchanged: MockItem = res.json().await.unwrap();
+    assert_eq!(unchanged.title, item.title);
+}
+
+#[tokio::test]
+async fn import_requires_login_and_sets_the_owner() {
+    let csv = "title,description\nimported by rest-importer,row\n";
+
+    let res = TestClient::new()
+        .request(Method::POST, "/import/items?format=csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let (client, user) = TestClient::registered("rest-importer").await;
+    let res = client
+        .request(Method::POST, "/import/items?format=csv")
+        .header(CSRF_HEADER, client.csrf_token())
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let item = client.find_item("imported by rest-importer").await;
+    assert_eq!(item.owner_id, Some(user.id));
+}
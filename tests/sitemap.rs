@@ -0,0 +1,60 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::{TestClient, SITEMAP_MAX_URLS};
+use regex::Regex;
+use reqwest::StatusCode;
+
+fn locs(xml: &str) -> Vec<String> {
+    Regex::new(r"<loc>(.*?)</loc>")
+        .unwrap()
+        .captures_iter(xml)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn sitemap_index_lists_every_item_page() {
+    let (client, _) = TestClient::registered("sitemap-editor").await;
+    let item = client.add_item_and_find("in the sitemap", "crawl me").await;
+
+    let res = TestClient::new().get("/sitemap.xml").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("application/xml"));
+    let index = res.text().await.unwrap();
+    assert!(index.contains("<sitemapindex"), "{index}");
+
+    let base_url = common::base_url();
+    let mut urls = vec![];
+    for sitemap in locs(&index) {
+        let path = sitemap.strip_prefix(base_url).expect("sitemaps are on the server");
+        let xml = TestClient::new().page(path).await;
+        assert!(xml.contains("<urlset"), "{xml}");
+        let page_urls = locs(&xml);
+        assert!(page_urls.len() <= SITEMAP_MAX_URLS, "{xml}");
+        urls.extend(page_urls);
+
+        if let Some(start) = xml.find(&format!("<loc>{base_url}/items/{}</loc>", item.id)) {
+            let url = &xml[start..xml[start..].find("</url>").unwrap() + start];
+            assert!(url.contains("<lastmod>"), "{url}");
+        }
+    }
+    assert!(urls.contains(&format!("{base_url}/")), "{urls:?}");
+    assert!(urls.contains(&format!("{base_url}/items/{}", item.id)), "{urls:?}");
+}
+
+#[tokio::test]
+async fn sitemaps_past_the_last_one_are_not_found() {
+    let res = TestClient::new().get("/sitemaps/100000.xml").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn robots_txt_points_to_the_sitemap() {
+    let res = TestClient::new().get("/robots.txt").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let robots = res.text().await.unwrap();
+    assert!(robots.contains("Disallow: /api/"), "{robots}");
+    assert!(robots.contains(&format!("Sitemap: {}/sitemap.xml", common::base_url())), "{robots}");
+}
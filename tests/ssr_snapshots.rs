@@ -0,0 +1,86 @@
+//! The pages render the items of `tests/fixtures/snapshot_items.sql` instead of the ones
+//! Items.sqlite comes with.
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::snapshot::{assert_snapshot, render};
+use common::TestClient;
+use leptos::*;
+use leptos_playground::auth::AuthContext;
+use leptos_playground::items::{MockItem, MockItemProps, RemoveItem};
+use leptos_playground::pagination::pagination_components::{
+    Pagination, PaginationProps, PaginationStateContext,
+};
+use leptos_router::*;
+
+/// Stands in for `Items`, reporting a fixed number of items to the surrounding `Pagination`.
+#[component]
+fn ElementCount(cx: Scope, count: usize) -> impl IntoView {
+    let pagination = use_context::<PaginationStateContext>(cx).unwrap();
+    pagination.set_pagination_state.update(|ps| ps.set_element_count(count));
+    view! { cx, <p>{format!("{count} items")}</p> }
+}
+
+async fn render_pagination(path: &str, count: usize) -> String {
+    render(path, move |cx| {
+        view! { cx,
+            <Router>
+                <Pagination
+                    pagination_link=Box::new(|page, page_size| format!("/items?page={page}&page_size={page_size}"))
+                    page_query_param="page".to_string()
+                    page_size_query_param="page_size".to_string()>
+                    <ElementCount count=count/>
+                </Pagination>
+            </Router>
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn pagination_first_page() {
+    assert_snapshot("pagination_first_page", &render_pagination("/items", 23).await);
+}
+
+#[tokio::test]
+async fn pagination_middle_page() {
+    assert_snapshot(
+        "pagination_middle_page",
+        &render_pagination("/items?page=3&page_size=2", 23).await,
+    );
+}
+
+#[tokio::test]
+async fn mock_item_as_anonymous() {
+    let html = render("/items", |cx| {
+        provide_context(cx, AuthContext::new(cx));
+        let item = MockItem {
+            id: 7,
+            title: "Snapshot title".to_string(),
+            description: "Snapshot description".to_string(),
+            owner_id: Some(1),
+        };
+        let remove_item = create_server_action::<RemoveItem>(cx);
+        view! { cx, <Router><MockItem item=item remove_item=remove_item/></Router> }
+    })
+    .await;
+    assert_snapshot("mock_item_as_anonymous", &html);
+}
+
+#[tokio::test]
+async fn app_routes() {
+    common::use_fixture(include_str!("fixtures/snapshot_items.sql"));
+    let client = TestClient::new();
+    for (name, path) in [
+        ("app_home", "/"),
+        ("app_login", "/login"),
+        ("app_register", "/register"),
+        ("app_items", "/items"),
+        ("app_items_page_2", "/items?page=2&page_size=2"),
+        ("app_item", "/items/1"),
+        ("app_trash", "/items/trash"),
+    ] {
+        assert_snapshot(name, &client.page(path).await);
+    }
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TestClient;
+use leptos_playground::authz::AuthError;
+use leptos_playground::trash::{GetDeletedItems, RestoreItem};
+
+#[tokio::test]
+async fn only_the_owner_restores_a_deleted_item() {
+    let (owner, _) = TestClient::registered("trash-owner").await;
+    let item = owner.add_item_and_find("trashed item", "to be restored").await;
+    owner.remove_item(item.id).await.unwrap();
+
+    let (deleted, _) = owner.call(GetDeletedItems { page: 1, page_size: 1000 }).await.unwrap();
+    assert!(deleted.iter().any(|deleted| deleted.id == item.id));
+
+    let (other, _) = TestClient::registered("trash-other").await;
+    let (deleted, _) = other.call(GetDeletedItems { page: 1, page_size: 1000 }).await.unwrap();
+    assert!(!deleted.iter().any(|deleted| deleted.id == item.id), "{deleted:?}");
+    let err = other.call(RestoreItem { id: item.id as i32 }).await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::Forbidden), "{err}");
+
+    owner.call(RestoreItem { id: item.id as i32 }).await.unwrap();
+    assert_eq!(owner.get_item(item.id).await.unwrap().title, "trashed item");
+}
+
+#[tokio::test]
+async fn restoring_an_item_not_in_the_trash_is_not_found() {
+    let (owner, _) = TestClient::registered("trash-restorer").await;
+    let item = owner.add_item_and_find("untrashed item", "never deleted").await;
+
+    let err = owner.call(RestoreItem { id: item.id as i32 }).await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::NotFound), "{err}");
+    let err = owner.call(RestoreItem { id: i32::MAX }).await.unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::NotFound), "{err}");
+}
+
+#[tokio::test]
+async fn anonymous_users_cannot_list_the_trash() {
+    let err = TestClient::new()
+        .call(GetDeletedItems { page: 1, page_size: 10 })
+        .await
+        .unwrap_err();
+    assert_eq!(AuthError::from_server_fn_error(&err), Some(AuthError::LoginRequired), "{err}");
+}